@@ -4,26 +4,352 @@ use clap::{Parser, Subcommand};
 pub struct Args {
     #[command(subcommand)]
     pub command: Commands,
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+    /// Print errors to stderr as JSON objects
+    #[arg(long, global = true)]
+    pub json_errors: bool,
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
-    Encode {
-        file_path: String,
-        chunk_type: String,
-        message: String,
-        output_file: String,
-    },
-    Decode {
-        file_path: String,
-        chunk_type: String,
-    },
-    Remove {
-        file_path: String,
-        chunk_type: String,
-        output_file: String,
-    },
-    Print {
-        file_path: String,
-    },
+    Encode(EncodeArgs),
+    EncodeMany(EncodeManyArgs),
+    Decode(DecodeArgs),
+    Remove(RemoveArgs),
+    MoveChunk(MoveChunkArgs),
+    RenameType(RenameTypeArgs),
+    Clean(CleanArgs),
+    Minify(MinifyArgs),
+    Print(PrintArgs),
+    SuggestType(SuggestTypeArgs),
+    Entropy(EntropyArgs),
+    Stats(StatsArgs),
+    Find(FindArgs),
+    Watermark(WatermarkArgs),
+    CheckWatermark(CheckWatermarkArgs),
+    EncodeHash(EncodeHashArgs),
+    VerifyHash(VerifyHashArgs),
+    Diff(DiffArgs),
+    Pipe(PipeArgs),
+}
+
+#[derive(clap::Args, Default)]
+pub struct EncodeArgs {
+    pub file_path: String,
+    #[command(flatten)]
+    pub read: ReadArgs,
+    #[command(flatten)]
+    pub write: WriteArgs,
+    pub chunk_type: String,
+    pub message: String,
+    pub output_file: Option<String>,
+    #[arg(long)]
+    pub no_verify: bool,
+    #[arg(long)]
+    pub normalize: bool,
+    #[arg(long)]
+    pub frame: bool,
+    #[arg(long)]
+    pub compress: bool,
+    #[command(flatten)]
+    pub password: PasswordArgs,
+    /// Append the message to the data of the existing chunk of this type
+    #[arg(long)]
+    pub append_existing: bool,
+    /// XOR the message with this repeating key, an obfuscation rather than encryption
+    #[arg(long)]
+    pub xor_key: Option<String>,
+    /// Print the crc of the written chunk in hex
+    #[arg(long)]
+    pub print_crc: bool,
+}
+
+#[derive(clap::Args, Default)]
+pub struct EncodeManyArgs {
+    pub file_path: String,
+    #[command(flatten)]
+    pub read: ReadArgs,
+    #[command(flatten)]
+    pub write: WriteArgs,
+    /// A chunk to add, as <chunk_type>=<message>
+    #[arg(long = "set", required = true)]
+    pub sets: Vec<String>,
+    pub output_file: Option<String>,
+    #[arg(long)]
+    pub no_verify: bool,
+}
+
+#[derive(clap::Args, Default)]
+pub struct DecodeArgs {
+    pub file_path: String,
+    #[command(flatten)]
+    pub read: ReadArgs,
+    #[arg(required_unless_present = "trailing")]
+    pub chunk_type: Option<String>,
+    #[command(flatten)]
+    pub password: PasswordArgs,
+    /// Fail instead of showing hex when the data is not valid UTF-8
+    #[arg(long)]
+    pub strict: bool,
+    /// Match the chunk type ignoring ASCII case
+    #[arg(long)]
+    pub ignore_case: bool,
+    /// Undo the XOR obfuscation with this repeating key
+    #[arg(long)]
+    pub xor_key: Option<String>,
+    /// Show the bytes after IEND which are not chunks, instead of a chunk
+    #[arg(long, conflicts_with = "chunk_type")]
+    pub trailing: bool,
+    /// Show every chunk of the type instead of the first one
+    #[arg(long)]
+    pub all: bool,
+    /// Show the contents as a JSON array of strings
+    #[arg(long, requires = "all")]
+    pub json: bool,
+}
+
+#[derive(clap::Args, Default)]
+pub struct RemoveArgs {
+    pub file_path: String,
+    #[command(flatten)]
+    pub read: ReadArgs,
+    #[command(flatten)]
+    pub write: WriteArgs,
+    pub chunk_type: String,
+    pub output_file: Option<String>,
+    #[arg(long)]
+    pub no_verify: bool,
+    #[arg(long)]
+    pub normalize: bool,
+    /// Remove only a chunk whose data contains this pattern
+    #[arg(long)]
+    pub contains: Option<String>,
+    /// Read the pattern as hex digits
+    #[arg(long, requires = "contains")]
+    pub hex: bool,
+}
+
+#[derive(clap::Args, Default)]
+pub struct MoveChunkArgs {
+    pub file_path: String,
+    #[command(flatten)]
+    pub read: ReadArgs,
+    #[command(flatten)]
+    pub write: WriteArgs,
+    pub chunk_type: String,
+    pub to_index: usize,
+    pub output_file: Option<String>,
+    #[arg(long)]
+    pub no_verify: bool,
+}
+
+#[derive(clap::Args, Default)]
+pub struct RenameTypeArgs {
+    pub file_path: String,
+    #[command(flatten)]
+    pub read: ReadArgs,
+    #[command(flatten)]
+    pub write: WriteArgs,
+    pub old_type: String,
+    pub new_type: String,
+    pub output_file: Option<String>,
+    #[arg(long)]
+    pub no_verify: bool,
+}
+
+#[derive(clap::Args, Default)]
+pub struct CleanArgs {
+    pub file_path: String,
+    #[command(flatten)]
+    pub read: ReadArgs,
+    #[command(flatten)]
+    pub write: WriteArgs,
+    /// Drop every chunk of this type, can be given several times
+    #[arg(long = "exclude-type")]
+    pub exclude_types: Vec<String>,
+    pub output_file: Option<String>,
+    #[arg(long)]
+    pub no_verify: bool,
+}
+
+#[derive(clap::Args, Default)]
+pub struct MinifyArgs {
+    pub file_path: String,
+    #[command(flatten)]
+    pub read: ReadArgs,
+    #[command(flatten)]
+    pub write: WriteArgs,
+    pub output_file: Option<String>,
+    #[arg(long)]
+    pub no_verify: bool,
+}
+
+#[derive(clap::Args, Default)]
+pub struct PrintArgs {
+    #[arg(required = true)]
+    pub file_paths: Vec<String>,
+    #[command(flatten)]
+    pub read: ReadArgs,
+    /// Show chunks of corrupted files, marking the ones with invalid crc
+    #[arg(long)]
+    pub no_crc_check: bool,
+    /// Show chunk data as a hex and ascii dump
+    #[arg(long)]
+    pub pretty: bool,
+    /// Maximum number of dump lines per chunk
+    #[arg(long, default_value_t = 16, requires = "pretty")]
+    pub pretty_lines: usize,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(clap::Args, Default)]
+pub struct SuggestTypeArgs {
+    pub file_path: String,
+    #[command(flatten)]
+    pub read: ReadArgs,
+    #[arg(long, default_value_t = 5)]
+    pub count: usize,
+}
+
+#[derive(clap::Args, Default)]
+pub struct EntropyArgs {
+    #[arg(required = true)]
+    pub file_paths: Vec<String>,
+    #[command(flatten)]
+    pub read: ReadArgs,
+    /// Skip a file once scanning it takes longer than this
+    #[arg(long)]
+    pub timeout_ms: Option<u64>,
+    #[command(flatten)]
+    pub batch: BatchArgs,
+}
+
+#[derive(clap::Args, Default)]
+pub struct StatsArgs {
+    pub file_path: String,
+    #[command(flatten)]
+    pub read: ReadArgs,
+}
+
+#[derive(clap::Args, Default)]
+pub struct FindArgs {
+    pub file_path: String,
+    #[command(flatten)]
+    pub read: ReadArgs,
+    pub pattern: String,
+    /// Read the pattern as hex digits
+    #[arg(long)]
+    pub hex: bool,
+    /// Give up once scanning the file takes longer than this
+    #[arg(long)]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(clap::Args, Default)]
+pub struct WatermarkArgs {
+    pub file_path: String,
+    #[command(flatten)]
+    pub read: ReadArgs,
+    #[command(flatten)]
+    pub write: WriteArgs,
+    pub marker: String,
+    pub output_file: Option<String>,
+    #[arg(long)]
+    pub no_verify: bool,
+}
+
+#[derive(clap::Args, Default)]
+pub struct CheckWatermarkArgs {
+    pub file_path: String,
+    #[command(flatten)]
+    pub read: ReadArgs,
+    pub marker: String,
+}
+
+#[derive(clap::Args, Default)]
+pub struct EncodeHashArgs {
+    pub file_path: String,
+    #[command(flatten)]
+    pub read: ReadArgs,
+    #[command(flatten)]
+    pub write: WriteArgs,
+    pub output_file: Option<String>,
+    #[arg(long)]
+    pub no_verify: bool,
+}
+
+#[derive(clap::Args, Default)]
+pub struct VerifyHashArgs {
+    pub file_path: String,
+    #[command(flatten)]
+    pub read: ReadArgs,
+}
+
+#[derive(clap::Args, Default)]
+pub struct DiffArgs {
+    pub first_file: String,
+    pub second_file: String,
+    #[command(flatten)]
+    pub read: ReadArgs,
+    /// Also compare the data of chunks byte for byte, reporting the first differing offset
+    #[arg(long)]
+    pub compare_bytes: bool,
+}
+
+#[derive(clap::Args, Default)]
+pub struct PipeArgs {
+    /// The file to read, stdin when absent or "-"
+    pub input_file: Option<String>,
+    /// The file to write, stdout when absent or "-"
+    pub output_file: Option<String>,
+    #[command(flatten)]
+    pub read: ReadArgs,
+}
+
+#[derive(clap::Args, Clone, Default)]
+pub struct PasswordArgs {
+    /// The password, visible to other users in the process list, prefer the other ways outside scripts
+    #[arg(long, group = "password_source")]
+    pub password: Option<String>,
+    /// Read the password from this environment variable
+    #[arg(long, value_name = "VAR", group = "password_source")]
+    pub password_env: Option<String>,
+    /// Prompt for the password without echoing it
+    #[arg(long, group = "password_source")]
+    pub password_prompt: bool,
+}
+
+#[derive(clap::Args, Default)]
+pub struct BatchArgs {
+    /// Go on with the other files when one cannot be read
+    #[arg(long)]
+    pub ignore_errors: bool,
+    /// Write the status of each file as JSON to this path
+    #[arg(long)]
+    pub report_json: Option<String>,
+}
+
+#[derive(clap::Args, Default)]
+pub struct WriteArgs {
+    /// Copy the original file to <file_path>.bak before writing over it
+    #[arg(long)]
+    pub backup: bool,
+    /// Overwrite an existing backup
+    #[arg(long, requires = "backup")]
+    pub force: bool,
+}
+
+#[derive(clap::Args, Default)]
+pub struct ReadArgs {
+    /// Reject chunks declaring more data bytes than this
+    #[arg(long)]
+    pub max_chunk_data: Option<usize>,
+    /// Keep the stored crc of each chunk instead of rejecting wrong ones
+    #[arg(long)]
+    pub preserve_crc: bool,
+    /// Skip this many bytes before the png signature
+    #[arg(long, default_value_t = 0)]
+    pub offset: usize,
 }
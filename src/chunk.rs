@@ -1,6 +1,11 @@
+use std::io::Read;
+
+use super::chunk_ref::ChunkRef;
 use super::chunk_type::ChunkType;
+use super::hex::{hex_decode, hex_encode};
 use crate::result::*;
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct Chunk {
     data_length: u32,
     chunk_type: ChunkType,
@@ -9,14 +14,11 @@ pub struct Chunk {
 }
 
 impl Chunk {
+    pub const SECRET_MAGIC: [u8; 9] = *b"pngsecret";
+
     pub fn new(chunk_type: ChunkType, data: Vec<u8>) -> Self {
         let data_length = u32::try_from(data.len()).unwrap();
-
-        let iso_crc = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
-        let mut bytes = chunk_type.bytes().to_vec();
-        let mut clone_data = data.clone();
-        bytes.append(&mut clone_data);
-        let crc = iso_crc.checksum(&bytes);
+        let crc = png_crc(&chunk_type.bytes(), &data);
 
         Self {
             data_length,
@@ -26,6 +28,60 @@ impl Chunk {
         }
     }
 
+    pub fn new_text(chunk_type: ChunkType, text: &str) -> Self {
+        Self::new(chunk_type, text.as_bytes().to_owned())
+    }
+
+    /// Reads one chunk from the current position of `reader` and validates it.
+    pub fn try_from_reader<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut data_length = [0u8; 4];
+        reader.read_exact(&mut data_length)?;
+        Self::try_from_reader_after_length(reader, data_length, None, true)
+    }
+
+    pub(crate) fn try_from_reader_after_length<R: Read>(
+        reader: &mut R,
+        data_length: [u8; 4],
+        max_chunk_data: Option<usize>,
+        check_crc: bool,
+    ) -> Result<Self> {
+        let length = u64::from(u32::from_be_bytes(data_length));
+        if max_chunk_data.is_some_and(|max| length > max as u64) {
+            Err("chunk data exceeds limit")?
+        }
+        let mut bytes = data_length.to_vec();
+        // The declared length is not trusted for allocating, the buffer only
+        // grows with the bytes actually read.
+        reader.take(length + 8).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != length + 12 {
+            Err("wrong length for png file")?
+        }
+
+        Ok(ChunkRef::parse(&bytes, check_crc)?.into_chunk())
+    }
+
+    /// Changes the type and recomputes the crc. The mutators keep the length and
+    /// crc in line with the type and data, unlike replacing the fields by hand.
+    pub fn set_chunk_type(&mut self, chunk_type: ChunkType) {
+        let data = std::mem::take(&mut self.data);
+        *self = Self::new(chunk_type, data);
+    }
+
+    /// Replaces the data, updating the length and crc.
+    pub fn set_data(&mut self, data: Vec<u8>) {
+        *self = Self::new(self.chunk_type.clone(), data);
+    }
+
+    pub fn append_data(&mut self, data: &[u8]) {
+        let mut new_data = std::mem::take(&mut self.data);
+        new_data.extend_from_slice(data);
+        self.set_data(new_data);
+    }
+
+    pub fn is_secret_frame(&self) -> bool {
+        self.data.starts_with(&Self::SECRET_MAGIC)
+    }
+
     pub fn data_length(&self) -> u32 {
         self.data_length
     }
@@ -42,10 +98,61 @@ impl Chunk {
         self.crc
     }
 
+    /// Length, type bytes, data and crc in one call.
+    pub fn parts(&self) -> (u32, [u8; 4], &[u8], u32) {
+        (
+            self.data_length,
+            self.chunk_type.bytes(),
+            &self.data,
+            self.crc,
+        )
+    }
+
+    pub fn verify_crc(&self) -> bool {
+        png_crc(&self.chunk_type.bytes(), &self.data) == self.crc
+    }
+
     pub fn data_as_string(&self) -> Result<String> {
         Ok(String::from_utf8(self.data.to_owned())?)
     }
 
+    /// Length of the chunk in a png file, i.e. data plus length, type and crc.
+    pub fn total_len(&self) -> usize {
+        12 + self.data.len()
+    }
+
+    /// Shannon entropy of the data in bits per byte, between 0 and 8.
+    pub fn entropy(&self) -> f64 {
+        let mut counts = [0usize; 256];
+        for b in self.data() {
+            counts[*b as usize] += 1;
+        }
+
+        if self.data.is_empty() {
+            return 0.0;
+        }
+
+        let length = self.data.len() as f64;
+        counts
+            .iter()
+            .filter(|count| **count > 0)
+            .map(|count| {
+                let p = *count as f64 / length;
+                // p * log2(1 / p) rather than -p * log2(p), which is -0.0 for p = 1
+                p * (1.0 / p).log2()
+            })
+            .sum()
+    }
+
+    /// Hex of `as_bytes`, parsed back by `from_hex`.
+    pub fn to_hex(&self) -> String {
+        hex_encode(&self.as_bytes())
+    }
+
+    pub fn from_hex(s: &str) -> Result<Self> {
+        Self::try_from(&hex_decode(s)?[..])
+    }
+
     pub fn as_bytes(&self) -> Vec<u8> {
         self.data_length
             .to_be_bytes()
@@ -57,48 +164,31 @@ impl Chunk {
     }
 }
 
+/// The crc of a chunk, computed over its type and data bytes.
+pub fn png_crc(chunk_type: &[u8; 4], data: &[u8]) -> u32 {
+    let iso_crc = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+    let mut digest = iso_crc.digest();
+    digest.update(chunk_type);
+    digest.update(data);
+    digest.finalize()
+}
+
 impl TryFrom<&[u8]> for Chunk {
     type Error = Error;
 
     fn try_from(bytes: &[u8]) -> Result<Self> {
-        let length = bytes.len();
-
-        if length < 12 {
-            Err("cannot less than 12 bytes")?
-        }
-
-        let data_length: [u8; 4] = bytes[..4].to_owned().try_into().unwrap();
-        let data_length = u32::from_be_bytes(data_length);
-
-        let chunk_type: [u8; 4] = bytes[4..8].to_owned().try_into().unwrap();
-        let chunk_type = ChunkType::try_from(chunk_type)?;
-
-        if !chunk_type.is_valid() {
-            Err("invalid chunk type")?
-        }
-
-        let data = bytes[8..length - 4].to_owned();
-
-        if usize::try_from(data_length).unwrap() != data.len() {
-            Err("wrong data length")?
-        }
-
-        let crc: [u8; 4] = bytes[length - 4..length].to_owned().try_into().unwrap();
-        let crc: u32 = u32::from_be_bytes(crc);
-
-        let iso_crc = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
-        let computed_crc = iso_crc.checksum(&bytes[4..length - 4]);
+        Ok(ChunkRef::try_from(bytes)?.into_chunk())
+    }
+}
 
-        if crc != computed_crc {
-            Err("crc is wrong")?
+impl From<ChunkRef<'_>> for Chunk {
+    fn from(chunk: ChunkRef<'_>) -> Self {
+        Self {
+            data_length: chunk.data_length(),
+            chunk_type: chunk.chunk_type().clone(),
+            data: chunk.data().to_owned(),
+            crc: chunk.crc(),
         }
-
-        Ok(Self {
-            data_length,
-            chunk_type,
-            data,
-            crc,
-        })
     }
 }
 
@@ -107,7 +197,7 @@ impl std::fmt::Display for Chunk {
         let suffix = if self.data().len() > 5 { "..." } else { "" };
         let data = self
             .data()
-            .into_iter()
+            .iter()
             .take(5)
             .map(|b| format!("0x{:02x}", b))
             .chain(std::iter::once(suffix.to_string()))
@@ -117,10 +207,7 @@ impl std::fmt::Display for Chunk {
         write!(
             f,
             "Chunk:\n\tlength: {}\n\ttype: {}\n\tdata: {} \n\tcrc: {}",
-            self.data_length,
-            self.chunk_type.to_string(),
-            data,
-            self.crc
+            self.data_length, self.chunk_type, data, self.crc
         )
     }
 }
@@ -130,9 +217,9 @@ mod tests {
     use std::str::FromStr;
 
     use crate::chunk_type::ChunkType;
-    use crate::result::Result;
+    use crate::result::{Error, Result};
 
-    use super::Chunk;
+    use super::{png_crc, Chunk};
 
     fn testing_chunk_try_from_with(
         data_length: u32,
@@ -207,6 +294,25 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn err_try_from_wrong_crc_reports_both_crcs() {
+        let data_length: u32 = 9;
+        let chunk_type = "RUST".as_bytes();
+        let chunk_data = "test data".as_bytes();
+        let crc: u32 = 1;
+
+        let result = testing_chunk_try_from_with(data_length, chunk_type, chunk_data, crc);
+
+        assert!(matches!(
+            result,
+            Err(Error::CrcMismatch {
+                expected: 2799226543,
+                actual: 1,
+                ..
+            })
+        ));
+    }
+
     #[test]
     fn err_try_from_wrong_data_length() {
         let data_length: u32 = 1;
@@ -219,6 +325,18 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn err_try_from_data_length_beyond_buffer() {
+        let data_length: u32 = u32::MAX;
+        let chunk_type = "RuST".as_bytes();
+        let chunk_data = [];
+        let crc: u32 = 1;
+
+        let result = testing_chunk_try_from_with(data_length, chunk_type, &chunk_data, crc);
+
+        assert!(result.is_err());
+    }
+
     #[test]
     fn err_data_as_string_for_invalid_utf_8() {
         let data_length: u32 = 1;
@@ -233,6 +351,32 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn err_try_from_reader_with_huge_declared_length() {
+        let bytes: Vec<u8> = [0xff, 0xff, 0xff, 0xff]
+            .iter()
+            .chain(b"ruStshort")
+            .copied()
+            .collect();
+
+        let result = Chunk::try_from_reader(&mut &bytes[..]);
+
+        assert_eq!(result.unwrap_err().to_string(), "wrong length for png file");
+    }
+
+    #[test]
+    fn try_from_reader_at_chunk_boundary() {
+        let bytes = crate::png::tests::PNG_FILE;
+        let mut reader = std::io::Cursor::new(&bytes[..]);
+        reader.set_position(8 + 25);
+
+        let chunk = Chunk::try_from_reader(&mut reader).unwrap();
+
+        assert_eq!(chunk.chunk_type().to_string(), "IDAT");
+        assert_eq!(chunk.data_length(), 31);
+        assert_eq!(reader.position(), 8 + 25 + 43);
+    }
+
     #[test]
     fn new_chunk() {
         let chunk_type = ChunkType::from_str("RUST").unwrap();
@@ -244,6 +388,137 @@ mod tests {
         assert_eq!(chunk.crc(), 2799226543);
     }
 
+    #[test]
+    fn new_text_chunk() {
+        let chunk_type = ChunkType::from_str("RUST").unwrap();
+
+        let chunk = Chunk::new_text(chunk_type.clone(), "test data");
+
+        assert_eq!(
+            chunk,
+            Chunk::new(chunk_type, "test data".as_bytes().to_owned())
+        );
+    }
+
+    #[test]
+    fn parts_match_accessors() {
+        let chunk = Chunk::new(ChunkType::from_str("RUST").unwrap(), "test data".into());
+
+        let (data_length, chunk_type, data, crc) = chunk.parts();
+
+        assert_eq!(data_length, chunk.data_length());
+        assert_eq!(chunk_type, *b"RUST");
+        assert_eq!(data, b"test data");
+        assert_eq!(crc, 2799226543);
+    }
+
+    #[test]
+    fn framed_chunk_is_secret_frame() {
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        let data = Chunk::SECRET_MAGIC.iter().chain(b"test data").copied();
+        let chunk = Chunk::new(chunk_type, data.collect());
+
+        assert!(chunk.is_secret_frame());
+    }
+
+    #[test]
+    fn plain_chunk_is_not_secret_frame() {
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        let chunk = Chunk::new(chunk_type, "test data".into());
+
+        assert!(!chunk.is_secret_frame());
+    }
+
+    #[test]
+    fn append_data() {
+        let chunk_type = ChunkType::from_str("RUST").unwrap();
+        let mut chunk = Chunk::new(chunk_type, "test".into());
+
+        chunk.append_data(" data".as_bytes());
+
+        assert_eq!(chunk.data_as_string().unwrap(), "test data");
+        assert_eq!(chunk.data_length(), 9);
+        assert_eq!(chunk.crc(), 2799226543);
+    }
+
+    #[test]
+    fn set_chunk_type() {
+        let mut chunk = Chunk::new(ChunkType::from_str("RuST").unwrap(), "test data".into());
+
+        chunk.set_chunk_type(ChunkType::from_str("RUST").unwrap());
+
+        assert_eq!(chunk.chunk_type().to_string(), "RUST");
+        assert_eq!(chunk.crc(), 2799226543);
+    }
+
+    #[test]
+    fn set_data() {
+        let mut chunk = Chunk::new(ChunkType::from_str("RUST").unwrap(), "data".into());
+
+        chunk.set_data("test data".into());
+
+        assert!(chunk.verify_crc());
+        assert_eq!(chunk.data_length(), 9);
+        assert_eq!(chunk.crc(), 2799226543);
+    }
+
+    #[test]
+    fn entropy_of_repeated_byte() {
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        let chunk = Chunk::new(chunk_type, vec![0x41; 1024]);
+
+        assert!(chunk.entropy().abs() < 1e-9);
+        assert!(chunk.entropy().is_sign_positive());
+    }
+
+    #[test]
+    fn entropy_of_uniform_bytes() {
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        let data = (0..=255u8).cycle().take(256 * 16).collect();
+        let chunk = Chunk::new(chunk_type, data);
+
+        assert!((chunk.entropy() - 8.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn entropy_of_empty_data() {
+        let chunk_type = ChunkType::from_str("IEND").unwrap();
+        let chunk = Chunk::new(chunk_type, vec![]);
+
+        assert_eq!(chunk.entropy(), 0.0);
+        assert!(chunk.entropy().is_sign_positive());
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), "test data".into());
+
+        let hex = chunk.to_hex();
+        let parsed = Chunk::from_hex(&hex).unwrap();
+
+        assert!(hex.starts_with("0000000972755374"));
+        assert_eq!(parsed.as_bytes(), chunk.as_bytes());
+    }
+
+    #[test]
+    fn err_from_invalid_hex() {
+        assert!(Chunk::from_hex("0000000").is_err());
+        assert!(Chunk::from_hex("zz").is_err());
+    }
+
+    #[test]
+    fn total_len_matches_as_bytes() {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), "test data".into());
+
+        assert_eq!(chunk.total_len(), 21);
+        assert_eq!(chunk.total_len(), chunk.as_bytes().len());
+    }
+
+    #[test]
+    fn png_crc_of_type_and_data() {
+        assert_eq!(png_crc(b"RUST", b"test data"), 2799226543);
+    }
+
     #[test]
     pub fn test_chunk_trait_impls() {
         let data_length: u32 = 9;
@@ -0,0 +1,163 @@
+use std::io::Read;
+
+use crate::{chunk::Chunk, png::Png, result::*};
+
+pub struct ChunkReader<R: Read> {
+    reader: R,
+    done: bool,
+    max_chunk_data: Option<usize>,
+    check_crc: bool,
+}
+
+impl<R: Read> ChunkReader<R> {
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+
+        if header != Png::STANDARD_HEADER {
+            Err("wrong header")?
+        }
+
+        Ok(Self {
+            reader,
+            done: false,
+            max_chunk_data: None,
+            check_crc: true,
+        })
+    }
+
+    /// Whether to reject chunks whose stored crc is wrong, which is the default.
+    pub fn check_crc(mut self, check_crc: bool) -> Self {
+        self.check_crc = check_crc;
+        self
+    }
+
+    /// Rejects chunks declaring more data bytes than this, before reading them.
+    pub fn max_chunk_data(mut self, max_chunk_data: Option<usize>) -> Self {
+        self.max_chunk_data = max_chunk_data;
+        self
+    }
+
+    fn read_chunk(&mut self) -> Result<Option<Chunk>> {
+        let mut data_length = [0u8; 4];
+        let mut filled = 0;
+        while filled < 4 {
+            match self.reader.read(&mut data_length[filled..])? {
+                0 if filled == 0 => return Ok(None),
+                0 => Err("wrong length for png file")?,
+                n => filled += n,
+            }
+        }
+
+        Ok(Some(Chunk::try_from_reader_after_length(
+            &mut self.reader,
+            data_length,
+            self.max_chunk_data,
+            self.check_crc,
+        )?))
+    }
+}
+
+impl<R: Read> Iterator for ChunkReader<R> {
+    type Item = Result<Chunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let result = self.read_chunk();
+        if !matches!(result, Ok(Some(_))) {
+            self.done = true;
+        }
+
+        result.transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::png::tests::PNG_FILE;
+
+    #[test]
+    fn read_chunks_of_image_file() {
+        let reader = ChunkReader::new(Cursor::new(&PNG_FILE[..])).unwrap();
+
+        let types: Vec<String> = reader
+            .map(|chunk| chunk.unwrap().chunk_type().to_string())
+            .collect();
+
+        assert_eq!(types, ["IHDR", "IDAT", "IEND"]);
+    }
+
+    #[test]
+    fn find_chunk_without_reading_all() {
+        let mut reader = ChunkReader::new(Cursor::new(&PNG_FILE[..])).unwrap();
+
+        let chunk = reader
+            .find(|chunk| chunk.as_ref().unwrap().chunk_type().to_string() == "IDAT")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(chunk.data_length(), 31);
+        assert_eq!(
+            reader.next().unwrap().unwrap().chunk_type().to_string(),
+            "IEND"
+        );
+    }
+
+    #[test]
+    fn err_read_truncated_chunk() {
+        let bytes = &PNG_FILE[..PNG_FILE.len() - 2];
+        let mut reader = ChunkReader::new(Cursor::new(bytes)).unwrap();
+
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn err_read_chunk_over_limit() {
+        let mut reader = ChunkReader::new(Cursor::new(&PNG_FILE[..]))
+            .unwrap()
+            .max_chunk_data(Some(30));
+
+        assert!(reader.next().unwrap().is_ok());
+        assert_eq!(
+            reader.next().unwrap().unwrap_err().to_string(),
+            "chunk data exceeds limit"
+        );
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn read_chunk_with_wrong_crc_without_check() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[32] ^= 0xff;
+
+        assert!(ChunkReader::new(Cursor::new(&bytes))
+            .unwrap()
+            .next()
+            .unwrap()
+            .is_err());
+        let chunk = ChunkReader::new(Cursor::new(&bytes))
+            .unwrap()
+            .check_crc(false)
+            .next()
+            .unwrap()
+            .unwrap();
+        assert!(!chunk.verify_crc());
+    }
+
+    #[test]
+    fn err_new_with_wrong_header() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[0] = 13;
+
+        assert!(ChunkReader::new(Cursor::new(bytes)).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{
+    chunk::{png_crc, Chunk},
+    chunk_type::ChunkType,
+    png::Png,
+    result::*,
+};
+
+pub struct ChunkRef<'a> {
+    data_length: u32,
+    chunk_type: ChunkType,
+    data: &'a [u8],
+    crc: u32,
+}
+
+impl<'a> ChunkRef<'a> {
+    pub fn data_length(&self) -> u32 {
+        self.data_length
+    }
+
+    pub fn chunk_type(&self) -> &ChunkType {
+        &self.chunk_type
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn crc(&self) -> u32 {
+        self.crc
+    }
+
+    pub fn into_chunk(self) -> Chunk {
+        Chunk::from(self)
+    }
+
+    pub(crate) fn parse(bytes: &'a [u8], check_crc: bool) -> Result<Self> {
+        let length = bytes.len();
+
+        if length < 12 {
+            Err("cannot less than 12 bytes")?
+        }
+
+        let data_length: [u8; 4] = bytes[..4].try_into().unwrap();
+        let data_length = u32::from_be_bytes(data_length);
+
+        if u64::from(data_length) + 12 != length as u64 {
+            Err("wrong data length")?
+        }
+
+        let chunk_type: [u8; 4] = bytes[4..8].try_into().unwrap();
+        let chunk_type = ChunkType::try_from(chunk_type)?;
+
+        if !chunk_type.is_valid() {
+            Err("invalid chunk type")?
+        }
+
+        let data = &bytes[8..length - 4];
+
+        let crc: [u8; 4] = bytes[length - 4..length].try_into().unwrap();
+        let crc: u32 = u32::from_be_bytes(crc);
+
+        let computed_crc = png_crc(&chunk_type.bytes(), data);
+
+        if check_crc && crc != computed_crc {
+            Err(Error::CrcMismatch {
+                chunk_type: chunk_type.to_string(),
+                expected: computed_crc,
+                actual: crc,
+            })?
+        }
+
+        Ok(Self {
+            data_length,
+            chunk_type,
+            data,
+            crc,
+        })
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ChunkRef<'a> {
+    type Error = Error;
+
+    fn try_from(bytes: &'a [u8]) -> Result<Self> {
+        Self::parse(bytes, true)
+    }
+}
+
+/// Iterates over the chunks of a png buffer without copying their data.
+pub struct ChunkRefs<'a> {
+    bytes: &'a [u8],
+    done: bool,
+}
+
+impl<'a> ChunkRefs<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Self> {
+        if bytes.len() < 8 || bytes[..8] != Png::STANDARD_HEADER {
+            Err("wrong header")?
+        }
+
+        Ok(Self {
+            bytes: &bytes[8..],
+            done: false,
+        })
+    }
+}
+
+impl<'a> Iterator for ChunkRefs<'a> {
+    type Item = Result<ChunkRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.bytes.is_empty() {
+            return None;
+        }
+
+        let chunk_length = match self.bytes.get(..4) {
+            Some(data_length) => 12 + u32::from_be_bytes(data_length.try_into().unwrap()) as usize,
+            None => usize::MAX,
+        };
+        if chunk_length > self.bytes.len() {
+            self.done = true;
+            return Some(Err("wrong length for png file".into()));
+        }
+
+        let (bytes, rest) = self.bytes.split_at(chunk_length);
+        self.bytes = rest;
+
+        let result = ChunkRef::try_from(bytes);
+        self.done = result.is_err();
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::PNG_FILE;
+
+    #[test]
+    fn chunk_refs_of_image_file() {
+        let chunks: Vec<ChunkRef> = ChunkRefs::new(&PNG_FILE)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+
+        let types: Vec<String> = chunks
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        let lengths: Vec<u32> = chunks.iter().map(|chunk| chunk.data_length()).collect();
+
+        assert_eq!(types, ["IHDR", "IDAT", "IEND"]);
+        assert_eq!(lengths, [13, 31, 0]);
+        assert_eq!(chunks[1].data().as_ptr(), PNG_FILE[41..].as_ptr());
+    }
+
+    #[test]
+    fn into_owned_chunk() {
+        let chunk_ref = ChunkRefs::new(&PNG_FILE).unwrap().next().unwrap().unwrap();
+        let crc = chunk_ref.crc();
+        let data = chunk_ref.data();
+
+        let chunk = chunk_ref.into_chunk();
+
+        assert_eq!(chunk.chunk_type().to_string(), "IHDR");
+        assert_eq!(chunk.data(), data);
+        assert_eq!(chunk.crc(), crc);
+    }
+
+    #[test]
+    fn err_chunk_refs_of_truncated_file() {
+        let mut chunks = ChunkRefs::new(&PNG_FILE[..PNG_FILE.len() - 2]).unwrap();
+
+        assert!(chunks.next().unwrap().is_ok());
+        assert!(chunks.next().unwrap().is_ok());
+        assert!(chunks.next().unwrap().is_err());
+        assert!(chunks.next().is_none());
+    }
+}
@@ -1,17 +1,38 @@
 use crate::result::*;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ChunkType {
     bytes: [u8; 4],
 }
 
 impl ChunkType {
+    /// Keeps the bytes as they are, for naming chunks of damaged files.
+    pub fn from_bytes_lossy(bytes: [u8; 4]) -> Self {
+        Self { bytes }
+    }
+
     pub fn bytes(&self) -> [u8; 4] {
         self.bytes
     }
 
+    /// Whether all bytes are letters, which always holds unless built by `from_bytes_lossy`.
+    pub fn is_well_formed(&self) -> bool {
+        self.bytes.into_iter().all(ChunkType::is_letter)
+    }
+
     fn is_letter(b: u8) -> bool {
-        (b >= 65 && b <= 90) || (b >= 97 && b <= 122)
+        (65..=90).contains(&b) || (97..=122).contains(&b)
+    }
+
+    /// Fails on the first byte which is not a letter, naming its position.
+    fn check_letters(bytes: &[u8]) -> Result<()> {
+        match bytes.iter().position(|b| !ChunkType::is_letter(*b)) {
+            Some(i) => Err(Error::ChunkTypeCharacter {
+                position: i + 1,
+                byte: bytes[i],
+            }),
+            None => Ok(()),
+        }
     }
 
     pub fn is_critical(&self) -> bool {
@@ -19,11 +40,19 @@ impl ChunkType {
         first >> 5 & 1 == 0
     }
 
+    pub fn is_ancillary(&self) -> bool {
+        !self.is_critical()
+    }
+
     pub fn is_public(&self) -> bool {
         let second = self.bytes[1];
         second >> 5 & 1 == 0
     }
 
+    pub fn is_private(&self) -> bool {
+        !self.is_public()
+    }
+
     pub fn is_reserved_bit_valid(&self) -> bool {
         let third = self.bytes[2];
         third >> 5 & 1 == 0
@@ -43,11 +72,8 @@ impl TryFrom<[u8; 4]> for ChunkType {
     type Error = Error;
 
     fn try_from(bytes: [u8; 4]) -> Result<Self> {
-        if bytes.into_iter().all(ChunkType::is_letter) {
-            Ok(Self { bytes })
-        } else {
-            Err("should be all letters !!")?
-        }
+        ChunkType::check_letters(&bytes)?;
+        Ok(Self { bytes })
     }
 }
 
@@ -55,21 +81,23 @@ impl std::str::FromStr for ChunkType {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self> {
-        let length = s.as_bytes().len();
+        let bytes: [u8; 4] = s
+            .as_bytes()
+            .try_into()
+            .map_err(|_| Error::ChunkTypeLength(s.len()))?;
 
-        if length == 4 && s.bytes().all(ChunkType::is_letter) {
-            let bytes = s.as_bytes().to_owned().try_into().unwrap();
-            Ok(Self { bytes })
-        } else {
-            Err("Should consist of 4 letters !!")?
-        }
+        ChunkType::check_letters(&bytes)?;
+        Ok(Self { bytes })
     }
 }
 
 impl std::fmt::Display for ChunkType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s = std::str::from_utf8(&self.bytes).unwrap();
-        write!(f, "{}", s)
+        // Bytes other than printable ASCII only come from `from_bytes_lossy`,
+        // they are escaped rather than written as they are.
+        self.bytes
+            .iter()
+            .try_for_each(|b| write!(f, "{}", std::ascii::escape_default(*b)))
     }
 }
 
@@ -112,6 +140,54 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn err_from_str_names_non_letter_position() {
+        let result = ChunkType::from_str("R2st");
+
+        assert!(matches!(
+            result,
+            Err(Error::ChunkTypeCharacter {
+                position: 2,
+                byte: b'2'
+            })
+        ));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "character 2 ('2') is not an ASCII letter"
+        );
+    }
+
+    #[test]
+    fn err_from_str_with_wrong_length() {
+        let result = ChunkType::from_str("RuSty");
+
+        assert!(matches!(result, Err(Error::ChunkTypeLength(5))));
+    }
+
+    #[test]
+    fn from_bytes_lossy_is_not_well_formed() {
+        let chunk_type = ChunkType::from_bytes_lossy([0, 65, 66, 67]);
+
+        assert!(!chunk_type.is_well_formed());
+        assert_eq!(chunk_type.bytes(), [0, 65, 66, 67]);
+        assert_eq!(
+            ChunkType::from_bytes_lossy([0xff, 65, 66, 67]).to_string(),
+            "\\xffABC"
+        );
+    }
+
+    #[test]
+    fn display_escapes_non_letter_bytes() {
+        let chunk_type = ChunkType::from_bytes_lossy([0, b'\n', 0xc3, b'A']);
+
+        assert_eq!(chunk_type.to_string(), "\\x00\\n\\xc3A");
+    }
+
+    #[test]
+    fn parsed_chunk_type_is_well_formed() {
+        assert!(ChunkType::from_str("RuSt").unwrap().is_well_formed());
+    }
+
     #[test]
     fn critical_case() {
         let chunk = ChunkType::from_str("Rust").unwrap();
@@ -140,6 +216,24 @@ mod tests {
         assert!(!chunk.is_public());
     }
 
+    #[test]
+    fn ancillary_is_not_critical() {
+        let critical = ChunkType::from_str("Rust").unwrap();
+        let ancillary = ChunkType::from_str("rust").unwrap();
+
+        assert!(!critical.is_ancillary());
+        assert!(ancillary.is_ancillary());
+    }
+
+    #[test]
+    fn private_is_not_public() {
+        let public = ChunkType::from_str("rUst").unwrap();
+        let private = ChunkType::from_str("rust").unwrap();
+
+        assert!(!public.is_private());
+        assert!(private.is_private());
+    }
+
     #[test]
     fn valid_reserved_bit_case() {
         let chunk = ChunkType::from_str("ruSt").unwrap();
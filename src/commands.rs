@@ -1,55 +1,2504 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Write};
 use std::str::FromStr;
+use std::time::{Duration, Instant};
 
-use pngsecret::{Chunk, ChunkType, Png, Result};
+use pngsecret::{Chunk, ChunkReader, ChunkType, Error, Png, Result, Secret};
+use sha2::{Digest, Sha256};
 
-pub fn encode(
-    file_path: String,
-    chunk_type: String,
-    message: String,
-    output_file: String,
+use crate::args::{
+    BatchArgs, CheckWatermarkArgs, CleanArgs, DecodeArgs, DiffArgs, EncodeArgs, EncodeHashArgs,
+    EncodeManyArgs, EntropyArgs, FindArgs, MinifyArgs, MoveChunkArgs, PasswordArgs, PipeArgs,
+    PrintArgs, ReadArgs, RemoveArgs, RenameTypeArgs, StatsArgs, SuggestTypeArgs, VerifyHashArgs,
+    WatermarkArgs, WriteArgs,
+};
+use crate::hex::{hex_decode, hex_encode};
+use crate::progress::{Progress, ProgressReader, ProgressWriter};
+
+/// Chunk type holding watermarks: ancillary, private and safe to copy.
+const WATERMARK_CHUNK_TYPE: &str = "wmRk";
+
+/// Chunk type holding the hash of the critical chunks: ancillary, private and unsafe to copy.
+const HASH_CHUNK_TYPE: &str = "imHS";
+
+/// The critical chunk types defined by the png specification, the only ones kept by minify.
+const STANDARD_CRITICAL_CHUNK_TYPES: [&str; 4] = ["IHDR", "PLTE", "IDAT", "IEND"];
+
+pub fn encode(args: EncodeArgs, quiet: bool) -> Result<()> {
+    encode_to(args, quiet, &mut std::io::stdout(), &mut log(quiet))
+}
+
+fn encode_to<W: Write, L: Write>(
+    args: EncodeArgs,
+    quiet: bool,
+    out: &mut W,
+    log: &mut L,
+) -> Result<()> {
+    let bytes = read_file(&args.file_path)?;
+    let mut png = parse_png(&bytes, &args.read)?;
+
+    let chunk_type = chunk_type_to_write(&args.chunk_type)?;
+    let mut data = args.message.as_bytes().to_owned();
+    if let Some(key) = &args.xor_key {
+        if args.append_existing {
+            Err("cannot append an obfuscated message to an existing chunk")?
+        }
+        xor(&mut data, key.as_bytes())?;
+    }
+    let password = read_password(&args.password)?;
+    let framed = args.frame || args.compress || password.is_some();
+
+    let chunk = if args.append_existing {
+        if framed {
+            Err("cannot append a framed secret to an existing chunk")?
+        }
+        let chunk = png
+            .chunk_by_type_mut(&args.chunk_type)
+            .ok_or_else(|| Error::ChunkNotFound(args.chunk_type.clone()))?;
+        if chunk.is_secret_frame() {
+            Err("cannot append to a framed secret")?
+        }
+        chunk.append_data(&data);
+        if chunk.is_secret_frame() {
+            Err("cannot append a message which would read as a framed secret")?
+        }
+        None
+    } else if framed {
+        let mut secret = Secret::new(data).compress(args.compress);
+        if let Some(password) = &password {
+            secret = secret.encrypt(password);
+        }
+        Some(secret.into_chunk(chunk_type)?)
+    } else {
+        Some(frame_if_ambiguous(Chunk::new(chunk_type, data))?)
+    };
+
+    let (action, crc) = match chunk {
+        Some(chunk) => {
+            let action = format!(
+                "Added chunk '{}' ({} bytes)",
+                args.chunk_type,
+                chunk.data_length()
+            );
+            let crc = chunk.crc();
+            png.append_chunk(chunk);
+            (action, crc)
+        }
+        None => (
+            format!(
+                "Appended {} bytes to chunk '{}'",
+                args.message.len(),
+                args.chunk_type
+            ),
+            png.chunk_by_type(&args.chunk_type).unwrap().crc(),
+        ),
+    };
+
+    if args.normalize {
+        png.normalize_order();
+    }
+
+    let output_file = output_path(&args.file_path, &args.output_file, &args.read, &args.write)?;
+    let written = write_png(&png, output_file, !args.no_verify, &args.read, !quiet)?;
+    if args.print_crc {
+        writeln!(out, "{:08x}", crc)?;
+    }
+    write_summary(log, &action, bytes.len(), written)
+}
+
+pub fn encode_many(args: EncodeManyArgs, quiet: bool) -> Result<()> {
+    let bytes = read_file(&args.file_path)?;
+    let mut png = parse_png(&bytes, &args.read)?;
+
+    let mut chunk_types = std::collections::HashSet::new();
+    let mut chunks = vec![];
+    for set in &args.sets {
+        let (chunk_type, message) = set
+            .split_once('=')
+            .ok_or_else(|| format!("expected <chunk_type>=<message>, got '{}'", set))?;
+        if !chunk_types.insert(chunk_type) {
+            Err(format!("chunk type '{}' is set more than once", chunk_type))?
+        }
+        chunks.push(frame_if_ambiguous(Chunk::new_text(
+            chunk_type_to_write(chunk_type)?,
+            message,
+        ))?);
+    }
+    let count = chunks.len();
+    png.append_chunks(chunks);
+
+    let output_file = output_path(&args.file_path, &args.output_file, &args.read, &args.write)?;
+    let written = write_png(&png, output_file, !args.no_verify, &args.read, !quiet)?;
+
+    let action = format!("Added {} chunks", count);
+    write_summary(&mut log(quiet), &action, bytes.len(), written)
+}
+
+pub fn decode(args: DecodeArgs) -> Result<()> {
+    decode_to(args, &mut std::io::stdout(), &mut std::io::stderr())
+}
+
+fn decode_to<W: Write, E: Write>(args: DecodeArgs, out: &mut W, err: &mut E) -> Result<()> {
+    if args.trailing {
+        let png = parse_png_lenient(&read_file(&args.file_path)?, &args.read)?;
+        out.write_all(png.trailing_data())?;
+        return Ok(());
+    }
+
+    let png = read_png(&args.file_path, &args.read)?;
+    let chunk_type = args.chunk_type.as_deref().ok_or("no chunk type given")?;
+
+    let chunks: Vec<&Chunk> = if args.ignore_case {
+        png.chunks_by_type_ignore_case(chunk_type).collect()
+    } else {
+        png.chunks_by_type(chunk_type).collect()
+    };
+    let count = if args.all { chunks.len() } else { 1 };
+    if chunks.is_empty() {
+        Err(Error::ChunkNotFound(chunk_type.to_string()))?
+    }
+
+    let password = read_password(&args.password)?;
+    let mut contents = vec![];
+    for chunk in &chunks[..count] {
+        let found_type = chunk.chunk_type().to_string();
+        if found_type != chunk_type {
+            writeln!(
+                err,
+                "warning: the case of a chunk type carries meaning, decoding '{}' for '{}'",
+                found_type, chunk_type
+            )?;
+        }
+        contents.push(chunk_content(chunk, &args, password.as_deref(), err)?);
+    }
+
+    if args.json {
+        let strings: Vec<String> = contents.iter().map(|content| to_json(content)).collect();
+        writeln!(out, "[{}]", strings.join(","))?;
+    } else {
+        writeln!(out, "The content is:\n{}", contents.join("\n---\n"))?;
+    }
+    Ok(())
+}
+
+fn chunk_content<E: Write>(
+    chunk: &Chunk,
+    args: &DecodeArgs,
+    password: Option<&str>,
+    err: &mut E,
+) -> Result<String> {
+    let mut data = if chunk.is_secret_frame() {
+        Secret::from_chunk(chunk, password)?.into_payload()
+    } else {
+        chunk.data().to_owned()
+    };
+    if let Some(key) = &args.xor_key {
+        xor(&mut data, key.as_bytes())?;
+    }
+
+    match String::from_utf8(data) {
+        Ok(content) => Ok(content),
+        Err(error) if args.strict => Err(error)?,
+        Err(error) => {
+            writeln!(err, "data is not valid UTF-8, showing hex")?;
+            Ok(hex_encode(error.as_bytes()))
+        }
+    }
+}
+
+pub fn remove(args: RemoveArgs, quiet: bool) -> Result<()> {
+    let bytes = read_file(&args.file_path)?;
+    let mut png = parse_png(&bytes, &args.read)?;
+
+    let pattern = match &args.contains {
+        Some(pattern) if args.hex => hex_decode(pattern)?,
+        Some(pattern) => pattern.as_bytes().to_owned(),
+        None => vec![],
+    };
+    let chunk = png.remove_chunk_matching(&args.chunk_type, |chunk| {
+        pattern.is_empty() || chunk.data().windows(pattern.len()).any(|w| w == pattern)
+    })?;
+
+    if args.normalize {
+        png.normalize_order();
+    }
+
+    let output_file = output_path(&args.file_path, &args.output_file, &args.read, &args.write)?;
+    let written = write_png(&png, output_file, !args.no_verify, &args.read, !quiet)?;
+
+    let action = format!(
+        "Removed chunk '{}' ({} bytes)",
+        args.chunk_type,
+        chunk.data_length()
+    );
+    write_summary(&mut log(quiet), &action, bytes.len(), written)
+}
+
+pub fn move_chunk(args: MoveChunkArgs, quiet: bool) -> Result<()> {
+    let mut png = read_png(&args.file_path, &args.read)?;
+
+    png.move_chunk(&args.chunk_type, args.to_index)?;
+
+    let output_file = output_path(&args.file_path, &args.output_file, &args.read, &args.write)?;
+    write_png(&png, output_file, !args.no_verify, &args.read, !quiet)?;
+    Ok(())
+}
+
+pub fn rename_type(args: RenameTypeArgs, quiet: bool) -> Result<()> {
+    let mut png = read_png(&args.file_path, &args.read)?;
+
+    let new_type = chunk_type_to_write(&args.new_type)?;
+    png.chunk_by_type_mut(&args.old_type)
+        .ok_or_else(|| Error::ChunkNotFound(args.old_type.clone()))?
+        .set_chunk_type(new_type);
+
+    let output_file = output_path(&args.file_path, &args.output_file, &args.read, &args.write)?;
+    write_png(&png, output_file, !args.no_verify, &args.read, !quiet)?;
+    Ok(())
+}
+
+pub fn clean(args: CleanArgs, quiet: bool) -> Result<()> {
+    let bytes = read_file(&args.file_path)?;
+    let mut png = parse_png(&bytes, &args.read)?;
+
+    let count = png.chunks().len();
+    png.retain(|chunk| {
+        let chunk_type = chunk.chunk_type().to_string();
+        !args.exclude_types.contains(&chunk_type)
+    });
+
+    let output_file = output_path(&args.file_path, &args.output_file, &args.read, &args.write)?;
+    let written = write_png(&png, output_file, !args.no_verify, &args.read, !quiet)?;
+
+    let action = format!("Removed {} chunks", count - png.chunks().len());
+    write_summary(&mut log(quiet), &action, bytes.len(), written)
+}
+
+/// Keeps only the standard critical chunks in canonical order, without trailing data.
+pub fn minify(args: MinifyArgs, quiet: bool) -> Result<()> {
+    let bytes = read_file(&args.file_path)?;
+    let mut png = parse_png_lenient(&bytes, &args.read)?;
+
+    let count = png.chunks().len();
+    png.retain(|chunk| {
+        STANDARD_CRITICAL_CHUNK_TYPES.contains(&chunk.chunk_type().to_string().as_str())
+    });
+    png.sanitize();
+    png.clear_trailing_data();
+    png.normalize_order();
+
+    let output_file = output_path(&args.file_path, &args.output_file, &args.read, &args.write)?;
+    let written = write_png(&png, output_file, !args.no_verify, &args.read, !quiet)?;
+
+    let action = format!("Removed {} chunks", count - png.chunks().len());
+    write_summary(&mut log(quiet), &action, bytes.len(), written)
+}
+
+pub fn print(mut args: PrintArgs, show_progress: bool) -> Result<()> {
+    args.file_paths = expand_globs(&args.file_paths)?;
+    print_files(
+        &args,
+        batch_progress(&args.file_paths, show_progress),
+        &mut std::io::stdout(),
+        &mut std::io::stderr(),
+    )
+}
+
+fn print_files<P: Write, W: Write, E: Write>(
+    args: &PrintArgs,
+    progress: Progress<P>,
+    out: &mut W,
+    err: &mut E,
 ) -> Result<()> {
-    let bytes = std::fs::read(file_path)?;
-    let mut png = Png::try_from(&bytes[..])?;
+    run_batch(&args.file_paths, &args.batch, progress, err, |file_path| {
+        print_file(args, file_path, out).map(|_| true)
+    })
+}
+
+fn print_file<W: Write>(args: &PrintArgs, file_path: &str, out: &mut W) -> Result<()> {
+    let png = if args.no_crc_check {
+        parse_png_lenient(&read_file(file_path)?, &args.read)?
+    } else {
+        read_png(file_path, &args.read)?
+    };
+
+    if args.file_paths.len() > 1 {
+        writeln!(out, "{}:", file_path)?;
+    }
+    for chunk in png.chunks() {
+        let mark = if chunk.verify_crc() { "" } else { " (invalid)" };
+        writeln!(out, "{}{}", chunk, mark)?;
+        if args.pretty {
+            for line in hex_dump(chunk.data(), args.pretty_lines) {
+                writeln!(out, "\t{}", line)?;
+            }
+        }
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
+
+pub fn suggest_type(args: SuggestTypeArgs) -> Result<()> {
+    let png = read_png(&args.file_path, &args.read)?;
+
+    for chunk_type in png.unused_safe_chunk_types(args.count) {
+        println!("{}", chunk_type);
+    }
+
+    Ok(())
+}
+
+pub fn entropy(mut args: EntropyArgs, show_progress: bool) -> Result<()> {
+    args.file_paths = expand_globs(&args.file_paths)?;
+    // a single file reports the bytes read instead of the files done
+    let file_progress = show_progress && args.file_paths.len() == 1;
+    entropy_to(
+        &args,
+        batch_progress(&args.file_paths, show_progress),
+        file_progress,
+        &mut std::io::stdout(),
+        &mut std::io::stderr(),
+    )
+}
+
+fn entropy_to<P: Write, W: Write, E: Write>(
+    args: &EntropyArgs,
+    progress: Progress<P>,
+    file_progress: bool,
+    out: &mut W,
+    err: &mut E,
+) -> Result<()> {
+    run_batch(&args.file_paths, &args.batch, progress, err, |file_path| {
+        entropy_of_file(args, file_path, file_progress, out)
+    })
+}
+
+/// Returns false when the file was skipped for taking too long.
+fn entropy_of_file<W: Write>(
+    args: &EntropyArgs,
+    file_path: &str,
+    show_progress: bool,
+    out: &mut W,
+) -> Result<bool> {
+    let deadline = Deadline::new(args.timeout_ms);
+    let mut seen_iend = false;
+
+    let mut lines = vec![];
+    // Chunks are read one at a time, so that a huge file is cut short too.
+    for chunk in read_chunks(file_path, &args.read, show_progress)? {
+        if deadline.is_expired() {
+            return Ok(false);
+        }
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            // what follows IEND is trailing data, as in `parse_png_lenient`
+            Err(_) if args.read.preserve_crc && seen_iend => break,
+            Err(error) => return Err(error),
+        };
+        seen_iend |= chunk.chunk_type().to_string() == "IEND";
+        lines.push(format!(
+            "{}\t{}\t{:.3}",
+            chunk.chunk_type(),
+            chunk.data_length(),
+            chunk.entropy()
+        ));
+    }
 
-    let chunk_type = ChunkType::from_str(&chunk_type)?;
-    let data = message.as_bytes().to_owned();
-    let chunk = Chunk::new(chunk_type, data);
+    if args.file_paths.len() > 1 {
+        writeln!(out, "{}:", file_path)?;
+    }
+    for line in lines {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(true)
+}
+
+pub fn stats(args: StatsArgs) -> Result<()> {
+    stats_to(args, &mut std::io::stdout())
+}
+
+fn stats_to<W: Write>(args: StatsArgs, out: &mut W) -> Result<()> {
+    let bytes = read_file(&args.file_path)?;
+    let png = parse_png(&bytes, &args.read)?;
+
+    let (critical, ancillary): (Vec<&Chunk>, Vec<&Chunk>) = png
+        .chunks()
+        .iter()
+        .partition(|chunk| chunk.chunk_type().is_critical());
+    let total_len = |chunks: Vec<&Chunk>| chunks.iter().map(|chunk| chunk.total_len()).sum();
+    let critical: usize = total_len(critical);
+    let ancillary: usize = total_len(ancillary);
+
+    writeln!(out, "total size: {} bytes", bytes.len())?;
+    writeln!(out, "header size: {} bytes", Png::STANDARD_HEADER.len())?;
+    writeln!(out, "chunks: {}", png.chunks().len())?;
+    writeln!(out, "critical chunks: {} bytes", critical)?;
+    writeln!(out, "ancillary chunks: {} bytes", ancillary)?;
+    if let Some(chunk) = png.chunks().iter().max_by_key(|chunk| chunk.data_length()) {
+        writeln!(
+            out,
+            "largest chunk: {} ({} bytes of data)",
+            chunk.chunk_type(),
+            chunk.data_length()
+        )?;
+    }
 
-    png.append_chunk(chunk);
+    Ok(())
+}
 
-    Ok(std::fs::write(output_file, png.as_bytes())?)
+pub fn find(args: FindArgs) -> Result<()> {
+    find_to(&args, &mut std::io::stdout(), &mut std::io::stderr())
 }
 
-pub fn decode(file_path: String, chunk_type: String) -> Result<()> {
-    let bytes = std::fs::read(file_path)?;
-    let png = Png::try_from(&bytes[..])?;
+fn find_to<W: Write, E: Write>(args: &FindArgs, out: &mut W, err: &mut E) -> Result<()> {
+    let deadline = Deadline::new(args.timeout_ms);
+    let png = read_png(&args.file_path, &args.read)?;
+
+    let pattern = if args.hex {
+        hex_decode(&args.pattern)?
+    } else {
+        args.pattern.as_bytes().to_owned()
+    };
+    if pattern.is_empty() {
+        Err("pattern should not be empty")?
+    }
 
-    let content = png
-        .chunk_by_type(&chunk_type)
-        .ok_or("no chunk with such type")?
-        .data_as_string()?;
+    let mut lines = vec![];
+    for (index, chunk) in png.chunks().iter().enumerate() {
+        if deadline.is_expired() {
+            writeln!(err, "{}: skipped, scanning took too long", args.file_path)?;
+            return Ok(());
+        }
+        for (offset, window) in chunk.data().windows(pattern.len()).enumerate() {
+            if window == pattern {
+                lines.push(format!(
+                    "{}\tchunk {}\toffset {}",
+                    chunk.chunk_type(),
+                    index,
+                    offset
+                ));
+            }
+        }
+    }
 
-    println!("The content is:\n{}", content);
+    for line in lines {
+        writeln!(out, "{}", line)?;
+    }
     Ok(())
 }
 
-pub fn remove(file_path: String, chunk_type: String, output_file: String) -> Result<()> {
-    let bytes = std::fs::read(file_path)?;
-    let mut png = Png::try_from(&bytes[..])?;
+pub fn watermark(args: WatermarkArgs, quiet: bool) -> Result<()> {
+    let mut png = read_png(&args.file_path, &args.read)?;
 
-    png.remove_chunk(&chunk_type)?;
+    let chunk_type = ChunkType::from_str(WATERMARK_CHUNK_TYPE)?;
+    png.append_chunk(Secret::new(args.marker.into_bytes()).into_chunk(chunk_type)?);
 
-    std::fs::write(output_file, png.as_bytes())?;
+    let output_file = output_path(&args.file_path, &args.output_file, &args.read, &args.write)?;
+    write_png(&png, output_file, !args.no_verify, &args.read, !quiet)?;
     Ok(())
 }
 
-pub fn print(file_path: String) -> Result<()> {
-    let bytes = std::fs::read(file_path)?;
-    let png = Png::try_from(&bytes[..])?;
+/// Prints whether the marker is present and returns it.
+pub fn check_watermark(args: CheckWatermarkArgs) -> Result<bool> {
+    let present = has_watermark(&args)?;
+    println!("{}", if present { "present" } else { "absent" });
+    Ok(present)
+}
 
+fn has_watermark(args: &CheckWatermarkArgs) -> Result<bool> {
+    let png = read_png(&args.file_path, &args.read)?;
+
+    let present = png
+        .chunks_by_type(WATERMARK_CHUNK_TYPE)
+        .filter_map(|chunk| Secret::from_chunk(chunk, None).ok())
+        .any(|secret| secret.payload() == args.marker.as_bytes());
+    Ok(present)
+}
+
+/// Stamps the hash of the critical chunks in a chunk of its own, replacing an older stamp.
+pub fn encode_hash(args: EncodeHashArgs, quiet: bool) -> Result<()> {
+    let mut png = read_png(&args.file_path, &args.read)?;
+
+    png.retain(|chunk| chunk.chunk_type().to_string() != HASH_CHUNK_TYPE);
+    let chunk_type = ChunkType::from_str(HASH_CHUNK_TYPE)?;
+    png.append_chunk(Chunk::new_text(chunk_type, &critical_hash(&png)));
+
+    let output_file = output_path(&args.file_path, &args.output_file, &args.read, &args.write)?;
+    write_png(&png, output_file, !args.no_verify, &args.read, !quiet)?;
+    Ok(())
+}
+
+/// Prints whether the critical chunks still match the stored hash and returns it.
+pub fn verify_hash(args: VerifyHashArgs) -> Result<bool> {
+    let matched = has_matching_hash(&args)?;
+    println!("{}", if matched { "unchanged" } else { "altered" });
+    Ok(matched)
+}
+
+fn has_matching_hash(args: &VerifyHashArgs) -> Result<bool> {
+    let png = read_png(&args.file_path, &args.read)?;
+
+    let stored = png
+        .chunk_by_type(HASH_CHUNK_TYPE)
+        .ok_or_else(|| Error::ChunkNotFound(HASH_CHUNK_TYPE.to_string()))?;
+    Ok(stored.data() == critical_hash(&png).as_bytes())
+}
+
+/// Hex of the SHA-256 over the bytes of every critical chunk, in order.
+fn critical_hash(png: &Png) -> String {
+    let mut hasher = Sha256::new();
     for chunk in png.chunks() {
-        println!("{}\n", chunk);
+        if chunk.chunk_type().is_critical() {
+            hasher.update(chunk.as_bytes());
+        }
+    }
+    hex_encode(&hasher.finalize())
+}
+
+/// Compares the chunks of two files by position and prints their differences,
+/// returning whether there were none.
+pub fn diff(args: DiffArgs) -> Result<bool> {
+    diff_to(&args, &mut std::io::stdout())
+}
+
+fn diff_to<W: Write>(args: &DiffArgs, out: &mut W) -> Result<bool> {
+    let first = read_png(&args.first_file, &args.read)?;
+    let second = read_png(&args.second_file, &args.read)?;
+
+    let mut lines = vec![];
+    let count = first.chunks().len().max(second.chunks().len());
+    for index in 0..count {
+        let (a, b) = match (first.chunk_at(index), second.chunk_at(index)) {
+            (Some(a), Some(b)) => (a, b),
+            (Some(a), None) => {
+                lines.push(format!(
+                    "chunk {}: {} only in first file",
+                    index,
+                    a.chunk_type()
+                ));
+                continue;
+            }
+            (None, Some(b)) => {
+                lines.push(format!(
+                    "chunk {}: {} only in second file",
+                    index,
+                    b.chunk_type()
+                ));
+                continue;
+            }
+            (None, None) => unreachable!(),
+        };
+
+        if a.chunk_type() != b.chunk_type() {
+            lines.push(format!(
+                "chunk {}: type {} vs {}",
+                index,
+                a.chunk_type(),
+                b.chunk_type()
+            ));
+            continue;
+        }
+        if a.data_length() != b.data_length() {
+            lines.push(format!(
+                "chunk {} ({}): length {} vs {}",
+                index,
+                a.chunk_type(),
+                a.data_length(),
+                b.data_length()
+            ));
+        }
+        if a.crc() != b.crc() {
+            lines.push(format!(
+                "chunk {} ({}): crc {:08x} vs {:08x}",
+                index,
+                a.chunk_type(),
+                a.crc(),
+                b.crc()
+            ));
+        }
+        if args.compare_bytes {
+            if let Some(offset) = first_difference(a.data(), b.data()) {
+                lines.push(format!(
+                    "chunk {} ({}): data differs at offset {}",
+                    index,
+                    a.chunk_type(),
+                    offset
+                ));
+            }
+        }
+    }
+
+    for line in &lines {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(lines.is_empty())
+}
+
+pub fn pipe(args: PipeArgs) -> Result<()> {
+    let bytes = match args.input_file.as_deref() {
+        Some(path) if path != "-" => read_file(path)?,
+        _ => {
+            let mut bytes = vec![];
+            std::io::stdin().read_to_end(&mut bytes)?;
+            bytes
+        }
+    };
+
+    let emitted = pipe_bytes(&bytes, &args.read)?;
+
+    match args.output_file.as_deref() {
+        Some(path) if path != "-" => {
+            std::fs::write(path, emitted).map_err(|source| Error::io(path, source))?
+        }
+        _ => std::io::stdout().write_all(&emitted)?,
+    }
+    Ok(())
+}
+
+/// Parses the png keeping the stored crcs and the trailing data, then emits
+/// it again, failing if that does not give back the very same bytes.
+fn pipe_bytes(bytes: &[u8], read: &ReadArgs) -> Result<Vec<u8>> {
+    let png = parse_png_lenient(bytes, read)?;
+    let emitted = png.as_bytes();
+    if emitted != skip_offset(bytes, read)? {
+        Err("the emitted bytes differ from the input")?
+    }
+    Ok(emitted)
+}
+
+pub fn error_json(error: &Error) -> String {
+    let mut fields = vec![
+        ("code", to_json(error.code())),
+        ("message", to_json(&error.to_string())),
+    ];
+    match error {
+        Error::Io {
+            path: Some(path), ..
+        } => fields.push(("path", to_json(path))),
+        Error::CrcMismatch {
+            chunk_type,
+            expected,
+            actual,
+        } => {
+            fields.push(("chunk_type", to_json(chunk_type)));
+            fields.push(("expected_crc", expected.to_string()));
+            fields.push(("actual_crc", actual.to_string()));
+        }
+        Error::ChunkNotFound(chunk_type) => fields.push(("chunk_type", to_json(chunk_type))),
+        Error::ChunkTypeLength(length) => fields.push(("length", length.to_string())),
+        Error::ChunkTypeCharacter { position, byte } => {
+            fields.push(("position", position.to_string()));
+            fields.push(("byte", byte.to_string()));
+        }
+        _ => {}
+    }
+
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(name, value)| format!("{}:{}", to_json(name), value))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+/// A command line that clap could not parse, as a JSON object like the other errors.
+pub fn usage_error_json(error: &clap::Error) -> String {
+    let message = error.render().to_string();
+    format!(
+        "{{{}:{},{}:{}}}",
+        to_json("code"),
+        to_json("usage"),
+        to_json("message"),
+        to_json(message.trim_end())
+    )
+}
+
+/// The first offset where the bytes differ, which is the end of the shorter
+/// one when it is a prefix of the other.
+fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    a.iter()
+        .zip(b)
+        .position(|(x, y)| x != y)
+        .or_else(|| (a.len() != b.len()).then(|| a.len().min(b.len())))
+}
+
+/// XORs the data with the repeating key, applying it twice gives the data back.
+fn xor(data: &mut [u8], key: &[u8]) -> Result<()> {
+    if key.is_empty() {
+        Err("xor key should not be empty")?
+    }
+
+    for (b, k) in data.iter_mut().zip(key.iter().cycle()) {
+        *b ^= k;
+    }
+    Ok(())
+}
+
+/// A JSON string literal holding `s`.
+fn to_json(s: &str) -> String {
+    let escaped: String = s
+        .chars()
+        .map(|c| match c {
+            '"' => "\\\"".to_string(),
+            '\\' => "\\\\".to_string(),
+            '\n' => "\\n".to_string(),
+            '\r' => "\\r".to_string(),
+            '\t' => "\\t".to_string(),
+            c if c.is_control() => format!("\\u{:04x}", c as u32),
+            c => c.to_string(),
+        })
+        .collect();
+    format!("\"{}\"", escaped)
+}
+
+/// Lines of offset, 16 hex bytes and their ascii, at most `max_lines` of them.
+fn hex_dump(bytes: &[u8], max_lines: usize) -> Vec<String> {
+    bytes
+        .chunks(16)
+        .take(max_lines)
+        .enumerate()
+        .map(|(i, line)| {
+            let hex: Vec<String> = line.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii: String = line
+                .iter()
+                .map(|b| match b {
+                    0x20..=0x7e => *b as char,
+                    _ => '.',
+                })
+                .collect();
+            format!("{:08x}  {:<47}  |{}|", i * 16, hex.join(" "), ascii)
+        })
+        .collect()
+}
+
+/// The chunk as it is, unless its data starts like a framed secret: it is
+/// then framed, so that decode does not misread it.
+fn frame_if_ambiguous(chunk: Chunk) -> Result<Chunk> {
+    if chunk.is_secret_frame() {
+        Secret::new(chunk.data().to_owned()).into_chunk(chunk.chunk_type().clone())
+    } else {
+        Ok(chunk)
+    }
+}
+
+/// The password from the environment variable, else from a prompt, else the
+/// one given by `--password`; `None` when no password is asked for.
+fn read_password(args: &PasswordArgs) -> Result<Option<String>> {
+    if let Some(var) = &args.password_env {
+        let password = std::env::var(var)
+            .map_err(|_| format!("environment variable {} is not set to a password", var))?;
+        return Ok(Some(password));
+    }
+    if args.password_prompt {
+        return Ok(Some(rpassword::prompt_password("Password: ")?));
+    }
+    Ok(args.password.clone())
+}
+
+/// Parses a chunk type which this tool can read back once written.
+fn chunk_type_to_write(s: &str) -> Result<ChunkType> {
+    let chunk_type = ChunkType::from_str(s)?;
+
+    if !chunk_type.is_reserved_bit_valid() {
+        Err(format!(
+            "invalid chunk type '{}', the third letter should be uppercase",
+            s
+        ))?
+    }
+    if !chunk_type.is_valid() {
+        Err(format!(
+            "invalid chunk type '{}', a critical chunk (uppercase first letter) \
+             cannot be safe to copy (lowercase fourth letter)",
+            s
+        ))?
+    }
+
+    Ok(chunk_type)
+}
+
+/// Expands the paths with glob patterns, for shells which do not do it.
+/// A pattern matching no file is an error.
+fn expand_globs(file_paths: &[String]) -> Result<Vec<String>> {
+    let mut expanded = vec![];
+
+    for file_path in file_paths {
+        if !file_path.contains(['*', '?', '[']) {
+            expanded.push(file_path.clone());
+            continue;
+        }
+
+        let paths = glob::glob(file_path)
+            .map_err(|error| format!("invalid glob pattern '{}': {}", file_path, error))?;
+        let count = expanded.len();
+        for path in paths {
+            let path = path.map_err(|error| {
+                let path = error.path().to_string_lossy().into_owned();
+                Error::io(&path, error.into())
+            })?;
+            expanded.push(path.to_string_lossy().into_owned());
+        }
+        if expanded.len() == count {
+            Err(format!("no file matches '{}'", file_path))?
+        }
+    }
+
+    Ok(expanded)
+}
+
+fn read_file(file_path: &str) -> Result<Vec<u8>> {
+    std::fs::read(file_path).map_err(|source| Error::io(file_path, source))
+}
+
+fn skip_offset<'a>(bytes: &'a [u8], read: &ReadArgs) -> Result<&'a [u8]> {
+    Ok(bytes
+        .get(read.offset..)
+        .ok_or("offset is beyond the end of file")?)
+}
+
+fn parse_png(bytes: &[u8], read: &ReadArgs) -> Result<Png> {
+    if read.preserve_crc {
+        parse_png_lenient(bytes, read)
+    } else {
+        Png::try_from_with_limits(skip_offset(bytes, read)?, read.max_chunk_data)
+    }
+}
+
+fn parse_png_lenient(bytes: &[u8], read: &ReadArgs) -> Result<Png> {
+    Png::try_from_lenient(skip_offset(bytes, read)?, read.max_chunk_data)
+}
+
+fn read_png(file_path: &str, read: &ReadArgs) -> Result<Png> {
+    parse_png(&read_file(file_path)?, read)
+}
+
+/// Files smaller than this are read too fast for their progress to be worth showing.
+const LARGE_FILE_SIZE: u64 = 1 << 20;
+
+const WRITE_PIECE_SIZE: usize = 64 << 10;
+
+/// A streaming reader of the chunks of a file, reporting the bytes read on
+/// stderr for large files when `show_progress` is set.
+type FileChunks = ChunkReader<BufReader<ProgressReader<File, std::io::Stderr>>>;
+
+/// Streams the chunks of the file instead of reading it whole.
+fn read_chunks(file_path: &str, read: &ReadArgs, show_progress: bool) -> Result<FileChunks> {
+    let file = File::open(file_path).map_err(|source| Error::io(file_path, source))?;
+    let size = file.metadata()?.len();
+    let progress = Progress::new(
+        std::io::stderr(),
+        show_progress && size >= LARGE_FILE_SIZE,
+        usize::try_from(size)?,
+    );
+    let mut reader = BufReader::new(ProgressReader::new(file, progress, file_path));
+    let offset = u64::try_from(read.offset)?;
+    if std::io::copy(&mut (&mut reader).take(offset), &mut std::io::sink())? < offset {
+        Err("offset is beyond the end of file")?
+    }
+
+    Ok(ChunkReader::new(reader)?
+        .max_chunk_data(read.max_chunk_data)
+        .check_crc(!read.preserve_crc))
+}
+
+/// Where a png is written, with the bytes to keep before it.
+struct Output<'a> {
+    path: &'a str,
+    /// The bytes skipped by `--offset`, kept when the input is edited in place.
+    prefix: Vec<u8>,
+    /// Where the input is backed up, once the png to write has been verified.
+    backup: Option<String>,
+}
+
+/// The file to write to, and the backup to make when editing the input in place.
+fn output_path<'a>(
+    file_path: &'a str,
+    output_file: &'a Option<String>,
+    read: &ReadArgs,
+    write: &WriteArgs,
+) -> Result<Output<'a>> {
+    let output_file = output_file.as_deref().unwrap_or(file_path);
+    if !is_same_file(output_file, file_path) {
+        return Ok(Output {
+            path: output_file,
+            prefix: vec![],
+            backup: None,
+        });
+    }
+
+    let backup = if write.backup {
+        let backup = format!("{}.bak", file_path);
+        if std::path::Path::new(&backup).exists() && !write.force {
+            Err(format!("backup '{}' already exists, use --force", backup))?
+        }
+        Some(backup)
+    } else {
+        None
+    };
+
+    let mut prefix = vec![];
+    let file = File::open(file_path).map_err(|source| Error::io(file_path, source))?;
+    file.take(u64::try_from(read.offset)?)
+        .read_to_end(&mut prefix)
+        .map_err(|source| Error::io(file_path, source))?;
+    Ok(Output {
+        path: output_file,
+        prefix,
+        backup,
+    })
+}
+
+/// Whether both paths lead to the same existing file, e.g. `./a.png` and `a.png`,
+/// or a symlink and its target.
+fn is_same_file(a: &str, b: &str) -> bool {
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Writes the png and returns the number of written bytes, checking first that
+/// it parses back. With `--preserve-crc` it is parsed back as leniently as the
+/// input was, wrong crcs and trailing data being kept on purpose. The backup is
+/// only made once that check passes. Large files report the bytes written on
+/// stderr when `show_progress` is set.
+fn write_png(
+    png: &Png,
+    output: Output,
+    verify: bool,
+    read: &ReadArgs,
+    show_progress: bool,
+) -> Result<usize> {
+    let bytes = png.as_bytes();
+    let output_file = output.path;
+
+    if verify {
+        let read = ReadArgs {
+            preserve_crc: read.preserve_crc,
+            ..Default::default()
+        };
+        parse_png(&bytes, &read)?.validate()?;
+    }
+
+    if let Some(backup) = &output.backup {
+        std::fs::copy(output_file, backup).map_err(|source| Error::io(backup, source))?;
+    }
+    let bytes = [output.prefix, bytes].concat();
+    let progress = Progress::new(
+        std::io::stderr(),
+        show_progress && bytes.len() as u64 >= LARGE_FILE_SIZE,
+        bytes.len(),
+    );
+    File::create(output_file)
+        .and_then(|file| {
+            let mut writer = ProgressWriter::new(file, progress, output_file);
+            // written in pieces, so that the progress has something to report
+            for piece in bytes.chunks(WRITE_PIECE_SIZE) {
+                writer.write_all(piece)?;
+            }
+            writer.flush()
+        })
+        .map_err(|source| Error::io(output_file, source))?;
+    Ok(bytes.len())
+}
+
+/// Progress on stderr, only worth showing when there are several files.
+fn batch_progress(file_paths: &[String], show_progress: bool) -> Progress<std::io::Stderr> {
+    Progress::new(
+        std::io::stderr(),
+        show_progress && file_paths.len() > 1,
+        file_paths.len(),
+    )
+}
+
+/// Runs `run` on each file, which returns false when it skips the file.
+/// With `--ignore-errors` it goes on past failing files and reports the counts.
+fn run_batch<P: Write, E: Write, F: FnMut(&str) -> Result<bool>>(
+    file_paths: &[String],
+    batch: &BatchArgs,
+    mut progress: Progress<P>,
+    err: &mut E,
+    mut run: F,
+) -> Result<()> {
+    let mut report = BatchReport::default();
+
+    for file_path in file_paths {
+        let result = run(file_path);
+        progress.advance(file_path);
+        match result {
+            Ok(true) => report.add(file_path, "ok", None),
+            Ok(false) => {
+                let message = "scanning took too long".to_string();
+                writeln!(err, "{}: skipped, {}", file_path, message)?;
+                report.add(file_path, "skipped", Some(message));
+            }
+            Err(error) => {
+                report.add(file_path, "failed", Some(error.to_string()));
+                if !batch.ignore_errors {
+                    report.write_json(batch)?;
+                    return Err(error);
+                }
+                writeln!(err, "{}: {}", file_path, error)?;
+            }
+        }
+    }
+
+    report.write_json(batch)?;
+    if batch.ignore_errors {
+        writeln!(
+            err,
+            "processed {}, skipped {}, failed {}",
+            report.count("ok"),
+            report.count("skipped"),
+            report.count("failed")
+        )?;
+    }
+
+    match report.count("failed") {
+        0 => Ok(()),
+        failed => Err(format!("{} files failed", failed))?,
+    }
+}
+
+/// The status of each file of a batch, with an optional message.
+#[derive(Default)]
+struct BatchReport {
+    files: Vec<(String, &'static str, Option<String>)>,
+}
+
+impl BatchReport {
+    fn add(&mut self, file_path: &str, status: &'static str, message: Option<String>) {
+        self.files.push((file_path.to_string(), status, message));
+    }
+
+    fn count(&self, status: &str) -> usize {
+        self.files.iter().filter(|file| file.1 == status).count()
     }
 
+    fn write_json(&self, batch: &BatchArgs) -> Result<()> {
+        let Some(path) = &batch.report_json else {
+            return Ok(());
+        };
+
+        let files: Vec<String> = self
+            .files
+            .iter()
+            .map(|(file_path, status, message)| {
+                format!(
+                    "{{\"path\":{},\"status\":\"{}\",\"message\":{}}}",
+                    to_json(file_path),
+                    status,
+                    message.as_deref().map_or("null".to_string(), to_json)
+                )
+            })
+            .collect();
+        let json = format!(
+            "{{\"files\":[{}],\"processed\":{},\"skipped\":{},\"failed\":{}}}\n",
+            files.join(","),
+            self.count("ok"),
+            self.count("skipped"),
+            self.count("failed")
+        );
+
+        std::fs::write(path, json).map_err(|source| Error::io(path, source))?;
+        Ok(())
+    }
+}
+
+/// The time budget of scanning one file, checked between chunks.
+struct Deadline(Option<Instant>);
+
+impl Deadline {
+    fn new(timeout_ms: Option<u64>) -> Self {
+        Self(timeout_ms.map(|ms| Instant::now() + Duration::from_millis(ms)))
+    }
+
+    fn is_expired(&self) -> bool {
+        self.0.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+fn log(quiet: bool) -> Box<dyn Write> {
+    if quiet {
+        Box::new(std::io::sink())
+    } else {
+        Box::new(std::io::stderr())
+    }
+}
+
+fn write_summary<L: Write>(log: &mut L, action: &str, before: usize, after: usize) -> Result<()> {
+    let change = match after.cmp(&before) {
+        std::cmp::Ordering::Greater => format!("file grew from {} to {} bytes", before, after),
+        std::cmp::Ordering::Less => format!("file shrank from {} to {} bytes", before, after),
+        std::cmp::Ordering::Equal => format!("file size stayed at {} bytes", after),
+    };
+
+    writeln!(log, "{}; {}.", action, change)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    const PNG_FILE: &[u8] = include_bytes!("../tests/data/image.png");
+
+    fn testing_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pngsecret-{}-{}", std::process::id(), name))
+    }
+
+    fn testing_png() -> Png {
+        let ihdr: Vec<u8> = [3u32.to_be_bytes(), 2u32.to_be_bytes()]
+            .concat()
+            .into_iter()
+            .chain([8, 2, 0, 0, 0])
+            .collect();
+
+        Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), ihdr),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), vec![0x08, 0x1d, 0x01]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![]),
+        ])
+    }
+
+    fn testing_file(name: &str) -> String {
+        let path = testing_path(name);
+        std::fs::write(&path, testing_png().as_bytes()).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    fn read_png_file(path: &str) -> Png {
+        let bytes = std::fs::read(path).unwrap();
+        Png::try_from(&bytes[..]).unwrap()
+    }
+
+    fn testing_encode_args(name: &str) -> EncodeArgs {
+        EncodeArgs {
+            file_path: testing_file(&format!("{}-input.png", name)),
+            chunk_type: "ruSt".into(),
+            message: "secret".into(),
+            output_file: Some(
+                testing_path(&format!("{}-output.png", name))
+                    .to_str()
+                    .unwrap()
+                    .to_string(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ok_encode_with_verification() {
+        let args = testing_encode_args("verify");
+        let output = args.output_file.clone().unwrap();
+
+        let result = encode(args, true);
+
+        assert!(result.is_ok());
+        let png = read_png_file(&output);
+        assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), b"secret");
+    }
+
+    #[test]
+    fn encode_with_summary() {
+        let args = testing_encode_args("summary");
+        let mut log = Vec::new();
+
+        encode_to(args, true, &mut Vec::new(), &mut log).unwrap();
+
+        let log = String::from_utf8(log).unwrap();
+        assert_eq!(
+            log,
+            "Added chunk 'ruSt' (6 bytes); file grew from 60 to 78 bytes.\n"
+        );
+    }
+
+    #[test]
+    fn encode_with_print_crc() {
+        let args = EncodeArgs {
+            print_crc: true,
+            ..testing_encode_args("print-crc")
+        };
+        let mut out = Vec::new();
+
+        encode_to(args, true, &mut out, &mut Vec::new()).unwrap();
+
+        let expected = pngsecret::png_crc(b"ruSt", b"secret");
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("{:08x}\n", expected)
+        );
+    }
+
+    #[test]
+    fn encode_with_normalize() {
+        let args = EncodeArgs {
+            normalize: true,
+            ..testing_encode_args("normalize")
+        };
+        let output = args.output_file.clone().unwrap();
+
+        encode(args, true).unwrap();
+
+        let png = read_png_file(&output);
+        assert_eq!(png.chunks()[2].chunk_type().to_string(), "ruSt");
+        assert_eq!(png.chunks()[3].chunk_type().to_string(), "IEND");
+    }
+
+    #[test]
+    fn encode_with_frame() {
+        let args = EncodeArgs {
+            frame: true,
+            ..testing_encode_args("frame")
+        };
+        let output = args.output_file.clone().unwrap();
+
+        encode(args, true).unwrap();
+
+        let png = read_png_file(&output);
+        let chunk = png.chunk_by_type("ruSt").unwrap();
+        assert!(chunk.is_secret_frame());
+        assert_eq!(
+            Secret::from_chunk(chunk, None).unwrap().payload(),
+            b"secret"
+        );
+        assert!(!png.chunk_by_type("IDAT").unwrap().is_secret_frame());
+    }
+
+    #[test]
+    fn encode_with_compression_and_password() {
+        let args = EncodeArgs {
+            compress: true,
+            password: PasswordArgs {
+                password: Some("password".into()),
+                ..Default::default()
+            },
+            ..testing_encode_args("secret")
+        };
+        let output = args.output_file.clone().unwrap();
+
+        encode(args, true).unwrap();
+
+        let png = read_png_file(&output);
+        let chunk = png.chunk_by_type("ruSt").unwrap();
+        let secret = Secret::from_chunk(chunk, Some("password")).unwrap();
+        assert!(secret.is_compressed());
+        assert_eq!(secret.payload(), b"secret");
+    }
+
+    #[test]
+    fn encode_and_decode_message_starting_with_magic() {
+        let args = EncodeArgs {
+            message: "pngsecret is the tool".into(),
+            ..testing_encode_args("magic")
+        };
+        let output = args.output_file.clone().unwrap();
+        encode(args, true).unwrap();
+
+        let args = DecodeArgs {
+            file_path: output,
+            chunk_type: Some("ruSt".into()),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        decode_to(args, &mut out, &mut Vec::new()).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "The content is:\npngsecret is the tool\n"
+        );
+    }
+
+    #[test]
+    fn encode_and_decode_with_password_env() {
+        std::env::set_var("PNGSECRET_TEST_PW", "password");
+        let password = PasswordArgs {
+            password_env: Some("PNGSECRET_TEST_PW".into()),
+            ..Default::default()
+        };
+        let args = EncodeArgs {
+            password: password.clone(),
+            ..testing_encode_args("password-env")
+        };
+        let output = args.output_file.clone().unwrap();
+        encode(args, true).unwrap();
+
+        let args = DecodeArgs {
+            file_path: output,
+            chunk_type: Some("ruSt".into()),
+            password,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        decode_to(args, &mut out, &mut Vec::new()).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), "The content is:\nsecret\n");
+    }
+
+    #[test]
+    fn err_password_env_not_set() {
+        let args = PasswordArgs {
+            password_env: Some("PNGSECRET_TEST_PW_UNSET".into()),
+            ..Default::default()
+        };
+
+        assert!(read_password(&args).is_err());
+    }
+
+    #[test]
+    fn encode_many_in_one_pass() {
+        let output = testing_path("encode-many-output.png");
+        let args = EncodeManyArgs {
+            file_path: testing_file("encode-many-input.png"),
+            sets: vec!["ruSt=first".into(), "teSt=second=part".into()],
+            output_file: Some(output.to_str().unwrap().to_string()),
+            ..Default::default()
+        };
+
+        encode_many(args, true).unwrap();
+
+        let png = read_png_file(output.to_str().unwrap());
+        assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), b"first");
+        assert_eq!(png.chunk_by_type("teSt").unwrap().data(), b"second=part");
+    }
+
+    #[test]
+    fn err_encode_many_with_duplicate_type() {
+        let output = testing_path("encode-many-duplicate-output.png");
+        let _ = std::fs::remove_file(&output);
+        let args = EncodeManyArgs {
+            file_path: testing_file("encode-many-duplicate-input.png"),
+            sets: vec!["ruSt=first".into(), "ruSt=second".into()],
+            output_file: Some(output.to_str().unwrap().to_string()),
+            ..Default::default()
+        };
+
+        assert!(encode_many(args, true).is_err());
+        assert!(!output.exists());
+    }
+
+    #[test]
+    fn encode_and_decode_with_xor_key() {
+        let args = EncodeArgs {
+            xor_key: Some("key".into()),
+            ..testing_encode_args("xor")
+        };
+        let output = args.output_file.clone().unwrap();
+        encode(args, true).unwrap();
+
+        let decode_with = |key: &str| {
+            let mut out = Vec::new();
+            let args = DecodeArgs {
+                file_path: output.clone(),
+                chunk_type: Some("ruSt".into()),
+                xor_key: Some(key.into()),
+                ..Default::default()
+            };
+            decode_to(args, &mut out, &mut Vec::new()).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        assert_ne!(
+            read_png_file(&output).chunk_by_type("ruSt").unwrap().data(),
+            b"secret"
+        );
+        assert_eq!(decode_with("key"), "The content is:\nsecret\n");
+        assert_ne!(decode_with("other"), "The content is:\nsecret\n");
+    }
+
+    fn testing_decode_args(name: &str, data: &[u8]) -> DecodeArgs {
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new(
+            ChunkType::from_str("ruSt").unwrap(),
+            data.into(),
+        ));
+        let path = testing_path(name);
+        std::fs::write(&path, png.as_bytes()).unwrap();
+
+        DecodeArgs {
+            file_path: path.to_str().unwrap().to_string(),
+            chunk_type: Some("ruSt".into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn decode_invalid_utf_8_as_hex() {
+        let args = testing_decode_args("decode-hex.png", &[0x80, 0x01]);
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+
+        decode_to(args, &mut out, &mut err).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), "The content is:\n8001\n");
+        assert!(String::from_utf8(err).unwrap().contains("not valid UTF-8"));
+    }
+
+    #[test]
+    fn decode_with_ignore_case() {
+        let mut args = testing_decode_args("decode-ignore-case.png", b"secret");
+        args.chunk_type = Some("RUST".into());
+        args.ignore_case = true;
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+
+        decode_to(args, &mut out, &mut err).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), "The content is:\nsecret\n");
+        assert!(String::from_utf8(err)
+            .unwrap()
+            .contains("'ruSt' for 'RUST'"));
+    }
+
+    #[test]
+    fn err_decode_without_ignore_case() {
+        let mut args = testing_decode_args("decode-case.png", b"secret");
+        args.chunk_type = Some("RUST".into());
+
+        let result = decode_to(args, &mut Vec::new(), &mut Vec::new());
+
+        assert!(matches!(result, Err(Error::ChunkNotFound(_))));
+    }
+
+    #[test]
+    fn err_decode_invalid_utf_8_when_strict() {
+        let args = DecodeArgs {
+            strict: true,
+            ..testing_decode_args("decode-strict.png", &[0x80])
+        };
+
+        let result = decode_to(args, &mut Vec::new(), &mut Vec::new());
+
+        assert!(matches!(result, Err(Error::Utf8(_))));
+    }
+
+    fn testing_decode_all_args(name: &str) -> DecodeArgs {
+        let mut png = testing_png();
+        for content in ["first", "second \"quoted\"", "third"] {
+            png.append_chunk(Chunk::new_text(
+                ChunkType::from_str("teSt").unwrap(),
+                content,
+            ));
+        }
+        let path = testing_path(name);
+        std::fs::write(&path, png.as_bytes()).unwrap();
+
+        DecodeArgs {
+            file_path: path.to_str().unwrap().to_string(),
+            chunk_type: Some("teSt".into()),
+            all: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn decode_all() {
+        let args = testing_decode_all_args("decode-all.png");
+        let mut out = Vec::new();
+
+        decode_to(args, &mut out, &mut Vec::new()).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "The content is:\nfirst\n---\nsecond \"quoted\"\n---\nthird\n"
+        );
+    }
+
+    #[test]
+    fn decode_all_as_json() {
+        let args = DecodeArgs {
+            json: true,
+            ..testing_decode_all_args("decode-all-json.png")
+        };
+        let mut out = Vec::new();
+
+        decode_to(args, &mut out, &mut Vec::new()).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "[\"first\",\"second \\\"quoted\\\"\",\"third\"]\n"
+        );
+    }
+
+    #[test]
+    fn decode_trailing() {
+        let path = testing_path("decode-trailing.png");
+        let bytes: Vec<u8> = testing_png()
+            .as_bytes()
+            .into_iter()
+            .chain(*b"hidden\x00")
+            .collect();
+        std::fs::write(&path, bytes).unwrap();
+        let args = DecodeArgs {
+            file_path: path.to_str().unwrap().to_string(),
+            trailing: true,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+
+        decode_to(args, &mut out, &mut Vec::new()).unwrap();
+
+        assert_eq!(out, b"hidden\x00");
+    }
+
+    #[test]
+    fn encode_with_append_existing() {
+        let args = EncodeArgs {
+            message: "foo".into(),
+            ..testing_encode_args("append-existing")
+        };
+        let output = args.output_file.clone().unwrap();
+        encode(args, true).unwrap();
+
+        encode(
+            EncodeArgs {
+                file_path: output.clone(),
+                chunk_type: "ruSt".into(),
+                message: "bar".into(),
+                output_file: Some(output.clone()),
+                append_existing: true,
+                ..Default::default()
+            },
+            true,
+        )
+        .unwrap();
+
+        let mut out = Vec::new();
+        let decode_args = DecodeArgs {
+            file_path: output.clone(),
+            chunk_type: Some("ruSt".into()),
+            ..Default::default()
+        };
+        decode_to(decode_args, &mut out, &mut Vec::new()).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), "The content is:\nfoobar\n");
+        assert_eq!(read_png_file(&output).chunk_count_by_type("ruSt"), 1);
+    }
+
+    #[test]
+    fn encode_in_place_with_backup() {
+        let file = testing_file("backup.png");
+        let _ = std::fs::remove_file(format!("{}.bak", file));
+        let original = std::fs::read(&file).unwrap();
+        let args = EncodeArgs {
+            file_path: file.clone(),
+            output_file: None,
+            write: WriteArgs {
+                backup: true,
+                force: false,
+            },
+            ..testing_encode_args("backup")
+        };
+
+        encode(args, true).unwrap();
+
+        assert_eq!(std::fs::read(format!("{}.bak", file)).unwrap(), original);
+        assert!(read_png_file(&file).contains("ruSt"));
+    }
+
+    #[test]
+    fn encode_in_place_with_offset_keeps_prefix() {
+        let bytes: Vec<u8> = [0xaa; 16]
+            .into_iter()
+            .chain(testing_png().as_bytes())
+            .collect();
+        let path = testing_path("offset-in-place.png");
+        std::fs::write(&path, bytes).unwrap();
+        let file = path.to_str().unwrap().to_string();
+        let _ = std::fs::remove_file(format!("{}.bak", file));
+        let mut args = EncodeArgs {
+            file_path: file.clone(),
+            output_file: None,
+            write: WriteArgs {
+                backup: true,
+                force: false,
+            },
+            ..testing_encode_args("offset-in-place")
+        };
+        args.read.offset = 16;
+
+        encode(args, true).unwrap();
+
+        let bytes = std::fs::read(&file).unwrap();
+        assert_eq!(bytes[..16], [0xaa; 16]);
+        assert!(Png::try_from(&bytes[16..]).unwrap().contains("ruSt"));
+        assert_eq!(
+            std::fs::read(format!("{}.bak", file)).unwrap()[..16],
+            [0xaa; 16]
+        );
+    }
+
+    #[test]
+    fn encode_with_backup_when_output_is_input_by_another_path() {
+        let file = testing_file("backup-other-path.png");
+        let _ = std::fs::remove_file(format!("{}.bak", file));
+        let original = std::fs::read(&file).unwrap();
+        let path = std::path::Path::new(&file);
+        let other_path = path
+            .parent()
+            .unwrap()
+            .join(".")
+            .join(path.file_name().unwrap());
+        let args = EncodeArgs {
+            file_path: file.clone(),
+            output_file: Some(other_path.to_str().unwrap().to_string()),
+            write: WriteArgs {
+                backup: true,
+                force: false,
+            },
+            ..testing_encode_args("backup-other-path")
+        };
+
+        encode(args, true).unwrap();
+
+        assert_eq!(std::fs::read(format!("{}.bak", file)).unwrap(), original);
+        assert!(read_png_file(&file).contains("ruSt"));
+    }
+
+    #[test]
+    fn err_encode_with_existing_backup() {
+        let file = testing_file("backup-exists.png");
+        std::fs::write(format!("{}.bak", file), b"old backup").unwrap();
+        let args = EncodeArgs {
+            file_path: file.clone(),
+            output_file: None,
+            write: WriteArgs {
+                backup: true,
+                force: false,
+            },
+            ..testing_encode_args("backup-exists")
+        };
+
+        assert!(encode(args, true).is_err());
+        assert_eq!(
+            std::fs::read(format!("{}.bak", file)).unwrap(),
+            b"old backup"
+        );
+    }
+
+    #[test]
+    fn encode_with_preserve_crc() {
+        let args = testing_encode_args("preserve-crc");
+        let mut original = std::fs::read(&args.file_path).unwrap();
+        original[47] ^= 0xff;
+        std::fs::write(&args.file_path, &original).unwrap();
+        let output = args.output_file.clone().unwrap();
+
+        encode(
+            EncodeArgs {
+                read: ReadArgs {
+                    preserve_crc: true,
+                    ..Default::default()
+                },
+                ..args
+            },
+            true,
+        )
+        .unwrap();
+
+        let written = std::fs::read(output).unwrap();
+        assert!(Png::try_from(&original[..]).is_err());
+        assert_eq!(written[..original.len()], original[..]);
+    }
+
+    #[test]
+    fn encode_with_preserve_crc_keeps_trailing_data() {
+        let args = testing_encode_args("preserve-crc-trailing");
+        let mut original = std::fs::read(&args.file_path).unwrap();
+        original.extend(b"trailing data");
+        std::fs::write(&args.file_path, &original).unwrap();
+        let output = args.output_file.clone().unwrap();
+
+        encode(
+            EncodeArgs {
+                read: ReadArgs {
+                    preserve_crc: true,
+                    ..Default::default()
+                },
+                ..args
+            },
+            true,
+        )
+        .unwrap();
+
+        let written = std::fs::read(output).unwrap();
+        assert!(written.ends_with(b"trailing data"));
+    }
+
+    #[test]
+    fn err_encode_with_invalid_reserved_bit() {
+        let args = EncodeArgs {
+            chunk_type: "rust".into(),
+            ..testing_encode_args("reserved-bit")
+        };
+        let output = args.output_file.clone().unwrap();
+        let _ = std::fs::remove_file(&output);
+
+        let result = encode(args, true);
+
+        assert!(result.unwrap_err().to_string().contains("third letter"));
+        assert!(!std::path::Path::new(&output).exists());
+    }
+
+    #[test]
+    fn err_encode_with_append_existing_without_chunk() {
+        let args = EncodeArgs {
+            append_existing: true,
+            ..testing_encode_args("append-missing")
+        };
+
+        assert!(matches!(encode(args, true), Err(Error::ChunkNotFound(_))));
+    }
+
+    fn testing_remove_args(name: &str) -> RemoveArgs {
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new_text(
+            ChunkType::from_str("ruSt").unwrap(),
+            "keep",
+        ));
+        png.append_chunk(Chunk::new_text(
+            ChunkType::from_str("ruSt").unwrap(),
+            "drop",
+        ));
+        let input = testing_path(&format!("{}-input.png", name));
+        std::fs::write(&input, png.as_bytes()).unwrap();
+
+        RemoveArgs {
+            file_path: input.to_str().unwrap().to_string(),
+            chunk_type: "ruSt".into(),
+            output_file: Some(
+                testing_path(&format!("{}-output.png", name))
+                    .to_str()
+                    .unwrap()
+                    .to_string(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn remove_with_contains() {
+        let args = RemoveArgs {
+            contains: Some("ro".into()),
+            ..testing_remove_args("remove-contains")
+        };
+        let output = args.output_file.clone().unwrap();
+
+        remove(args, true).unwrap();
+
+        let png = read_png_file(&output);
+        assert_eq!(png.chunk_count_by_type("ruSt"), 1);
+        assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), b"keep");
+    }
+
+    #[test]
+    fn remove_with_hex_contains() {
+        let args = RemoveArgs {
+            contains: Some("6b65".into()),
+            hex: true,
+            ..testing_remove_args("remove-hex")
+        };
+        let output = args.output_file.clone().unwrap();
+
+        remove(args, true).unwrap();
+
+        let png = read_png_file(&output);
+        assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), b"drop");
+    }
+
+    #[test]
+    fn rename_type_in_place() {
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new_text(
+            ChunkType::from_str("teSt").unwrap(),
+            "secret",
+        ));
+        let path = testing_path("rename-type.png");
+        std::fs::write(&path, png.as_bytes()).unwrap();
+        let file = path.to_str().unwrap().to_string();
+
+        rename_type(
+            RenameTypeArgs {
+                file_path: file.clone(),
+                old_type: "teSt".into(),
+                new_type: "teXt".into(),
+                ..Default::default()
+            },
+            true,
+        )
+        .unwrap();
+
+        let png = read_png_file(&file);
+        assert!(!png.contains("teSt"));
+        assert!(png.chunk_by_type("teXt").unwrap().verify_crc());
+        let mut out = Vec::new();
+        let args = DecodeArgs {
+            file_path: file,
+            chunk_type: Some("teXt".into()),
+            ..Default::default()
+        };
+        decode_to(args, &mut out, &mut Vec::new()).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "The content is:\nsecret\n");
+    }
+
+    #[test]
+    fn err_rename_type_to_invalid_type() {
+        let args = RenameTypeArgs {
+            file_path: testing_decode_args("rename-invalid.png", b"secret").file_path,
+            old_type: "ruSt".into(),
+            new_type: "rust".into(),
+            ..Default::default()
+        };
+
+        assert!(rename_type(args, true).is_err());
+    }
+
+    #[test]
+    fn clean_with_exclude_type() {
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new_text(ChunkType::from_str("teSt").unwrap(), "a"));
+        png.append_chunk(Chunk::new_text(ChunkType::from_str("ruSt").unwrap(), "b"));
+        png.append_chunk(Chunk::new_text(ChunkType::from_str("teSt").unwrap(), "c"));
+        let input = testing_path("clean-input.png");
+        std::fs::write(&input, png.as_bytes()).unwrap();
+        let output = testing_path("clean-output.png");
+        let args = CleanArgs {
+            file_path: input.to_str().unwrap().to_string(),
+            exclude_types: vec!["teSt".into()],
+            output_file: Some(output.to_str().unwrap().to_string()),
+            ..Default::default()
+        };
+
+        clean(args, true).unwrap();
+
+        let png = read_png_file(output.to_str().unwrap());
+        assert!(!png.contains("teSt"));
+        assert!(png.contains("ruSt"));
+        assert_eq!(png.chunks().len(), 4);
+    }
+
+    #[test]
+    fn minify_keeps_critical_chunks() {
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new_text(
+            ChunkType::from_str("tEXt").unwrap(),
+            "a\0b",
+        ));
+        png.move_chunk("tEXt", 1).unwrap();
+        let input = testing_path("minify-input.png");
+        let bytes: Vec<u8> = png.as_bytes().into_iter().chain(*b"trailing").collect();
+        std::fs::write(&input, bytes).unwrap();
+        let output = testing_path("minify-output.png");
+        let args = MinifyArgs {
+            file_path: input.to_str().unwrap().to_string(),
+            output_file: Some(output.to_str().unwrap().to_string()),
+            ..Default::default()
+        };
+
+        minify(args, true).unwrap();
+
+        assert_eq!(std::fs::read(&output).unwrap(), testing_png().as_bytes());
+    }
+
+    #[test]
+    fn minify_drops_unknown_critical_chunks() {
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new(
+            ChunkType::from_str("RUST").unwrap(),
+            "unknown".into(),
+        ));
+        png.move_chunk("RUST", 1).unwrap();
+        let input = testing_path("minify-unknown-input.png");
+        std::fs::write(&input, png.as_bytes()).unwrap();
+        let output = testing_path("minify-unknown-output.png");
+        let args = MinifyArgs {
+            file_path: input.to_str().unwrap().to_string(),
+            output_file: Some(output.to_str().unwrap().to_string()),
+            ..Default::default()
+        };
+
+        minify(args, true).unwrap();
+
+        assert_eq!(std::fs::read(&output).unwrap(), testing_png().as_bytes());
+    }
+
+    #[test]
+    fn move_chunk_in_place() {
+        let file = testing_file("move-chunk.png");
+
+        move_chunk(
+            MoveChunkArgs {
+                file_path: file.clone(),
+                chunk_type: "IEND".into(),
+                to_index: 1,
+                no_verify: true,
+                ..Default::default()
+            },
+            true,
+        )
+        .unwrap();
+
+        let png = read_png_file(&file);
+        assert_eq!(png.chunks()[1].chunk_type().to_string(), "IEND");
+    }
+
+    #[test]
+    fn stats_of_bundled_png() {
+        let path = testing_path("stats.png");
+        std::fs::write(&path, PNG_FILE).unwrap();
+        let args = StatsArgs {
+            file_path: path.to_str().unwrap().to_string(),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+
+        stats_to(args, &mut out).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(
+            out,
+            "total size: 88 bytes\n\
+             header size: 8 bytes\n\
+             chunks: 3\n\
+             critical chunks: 80 bytes\n\
+             ancillary chunks: 0 bytes\n\
+             largest chunk: IDAT (31 bytes of data)\n"
+        );
+    }
+
+    #[test]
+    fn find_pattern_in_chunks() {
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new_text(
+            ChunkType::from_str("ruSt").unwrap(),
+            "a SECRET, another SECRET",
+        ));
+        let path = testing_path("find.png");
+        std::fs::write(&path, png.as_bytes()).unwrap();
+        let mut args = FindArgs {
+            file_path: path.to_str().unwrap().to_string(),
+            pattern: "SECRET".into(),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+
+        find_to(&args, &mut out, &mut Vec::new()).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "ruSt\tchunk 3\toffset 2\nruSt\tchunk 3\toffset 18\n"
+        );
+
+        args.pattern = "081d".into();
+        args.hex = true;
+        let mut out = Vec::new();
+        find_to(&args, &mut out, &mut Vec::new()).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), "IDAT\tchunk 1\toffset 0\n");
+    }
+
+    #[test]
+    fn entropy_of_empty_chunk_is_not_negative() {
+        let args = EntropyArgs {
+            file_paths: vec![testing_file("entropy-empty.png")],
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+
+        entropy_to(
+            &args,
+            Progress::new(Vec::new(), false, 1),
+            false,
+            &mut out,
+            &mut Vec::new(),
+        )
+        .unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.ends_with("IEND\t0\t0.000\n"));
+        assert!(!out.contains("-0.000"));
+    }
+
+    #[test]
+    fn entropy_skips_file_after_timeout() {
+        let mut png = testing_png();
+        let large_chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), vec![0x41; 1 << 20]);
+        png.append_chunks(std::iter::repeat_n(large_chunk, 64));
+        let large = testing_path("entropy-large.png");
+        std::fs::write(&large, png.as_bytes()).unwrap();
+        let args = EntropyArgs {
+            file_paths: vec![
+                large.to_str().unwrap().to_string(),
+                testing_file("entropy-small.png"),
+            ],
+            timeout_ms: Some(100),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+
+        entropy_to(
+            &args,
+            Progress::new(Vec::new(), false, 2),
+            false,
+            &mut out,
+            &mut err,
+        )
+        .unwrap();
+
+        let err = String::from_utf8(err).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(err.contains("entropy-large.png: skipped"));
+        assert!(!err.contains("entropy-small.png: skipped"));
+        assert_eq!(out.lines().count(), 1 + 3);
+        assert!(out.lines().next().unwrap().ends_with("entropy-small.png:"));
+
+        let args = EntropyArgs {
+            timeout_ms: None,
+            ..args
+        };
+        let mut out = Vec::new();
+        let mut stderr = Vec::new();
+        entropy_to(
+            &args,
+            Progress::new(&mut stderr, true, 2),
+            false,
+            &mut out,
+            &mut Vec::new(),
+        )
+        .unwrap();
+        let stderr = String::from_utf8(stderr).unwrap();
+        assert!(stderr.contains("[100%] 2/2 "));
+        assert!(stderr.ends_with("entropy-small.png\n"));
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 2 + 67 + 3);
+    }
+
+    #[test]
+    fn watermark_then_check() {
+        let file = testing_file("watermark.png");
+        watermark(
+            WatermarkArgs {
+                file_path: file.clone(),
+                marker: "author-tag".to_string(),
+                ..Default::default()
+            },
+            true,
+        )
+        .unwrap();
+
+        let mut args = CheckWatermarkArgs {
+            file_path: file,
+            marker: "author-tag".to_string(),
+            ..Default::default()
+        };
+        assert!(has_watermark(&args).unwrap());
+
+        args.marker = "other-tag".to_string();
+        assert!(!has_watermark(&args).unwrap());
+    }
+
+    #[test]
+    fn print_with_pretty() {
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new(
+            ChunkType::from_str("ruSt").unwrap(),
+            b"hi\x00there".to_vec(),
+        ));
+        let path = testing_path("pretty.png");
+        std::fs::write(&path, png.as_bytes()).unwrap();
+        let args = PrintArgs {
+            file_paths: vec![path.to_str().unwrap().to_string()],
+            pretty: true,
+            pretty_lines: 16,
+            ..Default::default()
+        };
+        let mut stdout = Vec::new();
+
+        print_files(
+            &args,
+            Progress::new(Vec::new(), false, 1),
+            &mut stdout,
+            &mut Vec::new(),
+        )
+        .unwrap();
+
+        let stdout = String::from_utf8(stdout).unwrap();
+        assert!(stdout.contains("00000000  68 69 00 74 68 65 72 65"));
+        assert!(stdout.contains("|hi.there|"));
+    }
+
+    #[test]
+    fn diff_with_compare_bytes() {
+        let first = testing_file("diff-first.png");
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new_text(
+            ChunkType::from_str("ruSt").unwrap(),
+            "secret",
+        ));
+        std::fs::write(&first, png.as_bytes()).unwrap();
+        png.remove_chunk("ruSt").unwrap();
+        png.append_chunk(Chunk::new_text(
+            ChunkType::from_str("ruSt").unwrap(),
+            "secrEt",
+        ));
+        let second = testing_path("diff-second.png");
+        std::fs::write(&second, png.as_bytes()).unwrap();
+        let mut args = DiffArgs {
+            first_file: first,
+            second_file: second.to_str().unwrap().to_string(),
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+
+        assert!(!diff_to(&args, &mut out).unwrap());
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("chunk 3 (ruSt): crc "));
+        assert!(!out.contains("offset"));
+
+        args.compare_bytes = true;
+        let mut out = Vec::new();
+        assert!(!diff_to(&args, &mut out).unwrap());
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.ends_with("chunk 3 (ruSt): data differs at offset 4\n"));
+
+        args.second_file = args.first_file.clone();
+        let mut out = Vec::new();
+        assert!(diff_to(&args, &mut out).unwrap());
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn pipe_keeps_bytes_with_wrong_crc_and_trailing_data() {
+        let mut bytes = testing_png().as_bytes();
+        bytes[47] ^= 1;
+        let input = testing_path("pipe-input.png");
+        let output = testing_path("pipe-output.png");
+
+        for trailing in [&b""[..], b"trailing data"] {
+            let input_bytes = [&bytes[..], trailing].concat();
+            std::fs::write(&input, &input_bytes).unwrap();
+
+            pipe(PipeArgs {
+                input_file: Some(input.to_str().unwrap().to_string()),
+                output_file: Some(output.to_str().unwrap().to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+
+            assert_eq!(std::fs::read(&output).unwrap(), input_bytes);
+        }
+    }
+
+    #[test]
+    fn first_difference_of_prefix() {
+        assert_eq!(first_difference(b"abc", b"abc"), None);
+        assert_eq!(first_difference(b"abc", b"abd"), Some(2));
+        assert_eq!(first_difference(b"ab", b"abc"), Some(2));
+    }
+
+    #[test]
+    fn hex_dump_is_capped() {
+        let lines = hex_dump(&[0x41; 40], 2);
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[1],
+            format!("00000010  {}  |{}|", ["41"; 16].join(" "), "A".repeat(16))
+        );
+    }
+
+    #[test]
+    fn encode_hash_then_verify() {
+        let file = testing_file("hash.png");
+        encode_hash(
+            EncodeHashArgs {
+                file_path: file.clone(),
+                ..Default::default()
+            },
+            true,
+        )
+        .unwrap();
+        let args = VerifyHashArgs {
+            file_path: file.clone(),
+            ..Default::default()
+        };
+
+        assert!(has_matching_hash(&args).unwrap());
+
+        let mut png = read_png_file(&file);
+        let idat = png.chunk_by_type_mut("IDAT").unwrap();
+        let mut data = idat.data().to_owned();
+        data[2] ^= 0xff;
+        idat.set_data(data);
+        std::fs::write(&file, png.as_bytes()).unwrap();
+
+        assert!(!has_matching_hash(&args).unwrap());
+    }
+
+    #[test]
+    fn print_batch_shows_each_file() {
+        let args = PrintArgs {
+            file_paths: vec![
+                testing_file("batch-first.png"),
+                testing_file("batch-second.png"),
+            ],
+            ..Default::default()
+        };
+        let mut stdout = Vec::new();
+
+        print_files(
+            &args,
+            Progress::new(Vec::new(), false, 2),
+            &mut stdout,
+            &mut Vec::new(),
+        )
+        .unwrap();
+
+        let stdout = String::from_utf8(stdout).unwrap();
+        assert!(args
+            .file_paths
+            .iter()
+            .all(|file| stdout.contains(file.as_str())));
+    }
+
+    #[test]
+    fn print_batch_with_ignore_errors() {
+        let corrupt = testing_path("batch-corrupt.png");
+        std::fs::write(&corrupt, b"not a png").unwrap();
+        let mut args = PrintArgs {
+            file_paths: vec![
+                testing_file("batch-good-first.png"),
+                corrupt.to_str().unwrap().to_string(),
+                testing_file("batch-good-second.png"),
+            ],
+            ..Default::default()
+        };
+        let progress = || Progress::new(Vec::new(), false, 3);
+
+        let mut stdout = Vec::new();
+        assert!(print_files(&args, progress(), &mut stdout, &mut Vec::new()).is_err());
+        assert!(!String::from_utf8(stdout)
+            .unwrap()
+            .contains("batch-good-second"));
+
+        args.batch.ignore_errors = true;
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        let result = print_files(&args, progress(), &mut stdout, &mut stderr);
+
+        assert!(result.is_err());
+        let stdout = String::from_utf8(stdout).unwrap();
+        assert!(stdout.contains("batch-good-first"));
+        assert!(stdout.contains("batch-good-second"));
+        let stderr = String::from_utf8(stderr).unwrap();
+        assert!(stderr.contains("batch-corrupt.png: wrong header"));
+        assert!(stderr.contains("processed 2, skipped 0, failed 1"));
+    }
+
+    #[test]
+    fn print_with_glob() {
+        let dir = testing_path("glob");
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["first.png", "second.png"] {
+            std::fs::write(dir.join(name), testing_png().as_bytes()).unwrap();
+        }
+        std::fs::write(dir.join("notes.txt"), b"not a png").unwrap();
+        let pattern = dir.join("*.png").to_str().unwrap().to_string();
+
+        let args = PrintArgs {
+            file_paths: expand_globs(&[pattern]).unwrap(),
+            ..Default::default()
+        };
+        let mut stdout = Vec::new();
+        print_files(
+            &args,
+            Progress::new(Vec::new(), false, 2),
+            &mut stdout,
+            &mut Vec::new(),
+        )
+        .unwrap();
+
+        assert_eq!(args.file_paths.len(), 2);
+        let stdout = String::from_utf8(stdout).unwrap();
+        assert!(stdout.contains("first.png:"));
+        assert!(stdout.contains("second.png:"));
+    }
+
+    #[test]
+    fn err_glob_matching_nothing() {
+        let pattern = testing_path("no-such-dir").join("*.png");
+
+        let result = expand_globs(&[pattern.to_str().unwrap().to_string()]);
+
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .starts_with("no file matches"));
+    }
+
+    #[test]
+    fn print_batch_with_report_json() {
+        let corrupt = testing_path("report-corrupt.png");
+        std::fs::write(&corrupt, b"not a png").unwrap();
+        let report = testing_path("report.json");
+        let args = PrintArgs {
+            file_paths: vec![
+                testing_file("report-good.png"),
+                corrupt.to_str().unwrap().to_string(),
+            ],
+            batch: BatchArgs {
+                ignore_errors: true,
+                report_json: Some(report.to_str().unwrap().to_string()),
+            },
+            ..Default::default()
+        };
+
+        let result = print_files(
+            &args,
+            Progress::new(Vec::new(), false, 2),
+            &mut Vec::new(),
+            &mut Vec::new(),
+        );
+
+        assert!(result.is_err());
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(report).unwrap()).unwrap();
+        let files = json["files"].as_array().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0]["path"], args.file_paths[0]);
+        assert_eq!(files[0]["status"], "ok");
+        assert!(files[0]["message"].is_null());
+        assert_eq!(files[1]["path"], args.file_paths[1]);
+        assert_eq!(files[1]["status"], "failed");
+        assert_eq!(files[1]["message"], "wrong header");
+        assert_eq!(json["processed"], 1);
+        assert_eq!(json["failed"], 1);
+    }
+
+    #[test]
+    fn print_with_no_crc_check() {
+        let mut bytes = testing_png().as_bytes();
+        let idat_crc = bytes.len() - 16;
+        bytes[idat_crc] ^= 0xff;
+        let path = testing_path("no-crc-check.png");
+        std::fs::write(&path, bytes).unwrap();
+        let args = PrintArgs {
+            file_paths: vec![path.to_str().unwrap().to_string()],
+            no_crc_check: true,
+            ..Default::default()
+        };
+        let mut stdout = Vec::new();
+
+        print_files(
+            &args,
+            Progress::new(Vec::new(), false, 1),
+            &mut stdout,
+            &mut Vec::new(),
+        )
+        .unwrap();
+
+        let stdout = String::from_utf8(stdout).unwrap();
+        let chunks: Vec<&str> = stdout.split("Chunk:").skip(1).collect();
+        assert_eq!(chunks.len(), 3);
+        assert!(!chunks[0].contains("(invalid)"));
+        assert!(chunks[1].contains("type: IDAT") && chunks[1].contains("(invalid)"));
+        assert!(!chunks[2].contains("(invalid)"));
+    }
+
+    #[test]
+    fn print_with_offset() {
+        let bytes: Vec<u8> = [0xaa; 16]
+            .into_iter()
+            .chain(testing_png().as_bytes())
+            .collect();
+        let path = testing_path("offset.png");
+        std::fs::write(&path, bytes).unwrap();
+        let mut args = PrintArgs {
+            file_paths: vec![path.to_str().unwrap().to_string()],
+            ..Default::default()
+        };
+        let mut stdout = Vec::new();
+
+        assert!(print_files(
+            &args,
+            Progress::new(Vec::new(), false, 1),
+            &mut stdout,
+            &mut Vec::new()
+        )
+        .is_err());
+
+        args.read.offset = 16;
+        print_files(
+            &args,
+            Progress::new(Vec::new(), false, 1),
+            &mut stdout,
+            &mut Vec::new(),
+        )
+        .unwrap();
+
+        let stdout = String::from_utf8(stdout).unwrap();
+        assert_eq!(stdout.matches("Chunk:").count(), 3);
+    }
+
+    #[test]
+    fn err_read_png_above_max_chunk_data() {
+        let file = testing_file("max-chunk-data.png");
+        let read = ReadArgs {
+            max_chunk_data: Some(4),
+            ..Default::default()
+        };
+
+        assert!(read_png(&file, &read).is_err());
+    }
+
+    #[test]
+    fn err_write_png_with_invalid_chunk() {
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new(
+            ChunkType::from_str("RUSt").unwrap(),
+            "test data".into(),
+        ));
+        let output = testing_path("verify-invalid.png");
+        let _ = std::fs::remove_file(&output);
+
+        let result = write_png(
+            &png,
+            Output {
+                path: output.to_str().unwrap(),
+                prefix: vec![],
+                backup: None,
+            },
+            true,
+            &ReadArgs::default(),
+            false,
+        );
+
+        assert!(result.is_err());
+        assert!(!output.exists());
+    }
+
+    #[test]
+    fn err_write_png_in_place_without_backup() {
+        let mut png = testing_png();
+        png.append_chunk(Chunk::new(
+            ChunkType::from_str("RUSt").unwrap(),
+            "test data".into(),
+        ));
+        let file = testing_file("verify-invalid-backup.png");
+        let backup = format!("{}.bak", file);
+        let _ = std::fs::remove_file(&backup);
+        let original = std::fs::read(&file).unwrap();
+        let write = WriteArgs {
+            backup: true,
+            force: false,
+        };
+        let output = output_path(&file, &None, &ReadArgs::default(), &write).unwrap();
+
+        let result = write_png(&png, output, true, &ReadArgs::default(), false);
+
+        assert!(result.is_err());
+        assert!(!std::path::Path::new(&backup).exists());
+        assert_eq!(std::fs::read(&file).unwrap(), original);
+    }
+}
@@ -0,0 +1,38 @@
+use crate::Result;
+
+/// Two lowercase hex digits for each byte.
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The bytes of a string of hex digits, in either case.
+pub(crate) fn hex_decode(s: &str) -> Result<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        Err("invalid hex string")?
+    }
+
+    Ok((0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_round_trip() {
+        let hex = hex_encode(&[0x00, 0x1d, 0xff]);
+
+        assert_eq!(hex, "001dff");
+        assert_eq!(hex_decode(&hex).unwrap(), [0x00, 0x1d, 0xff]);
+        assert_eq!(hex_decode("1DFF").unwrap(), [0x1d, 0xff]);
+    }
+
+    #[test]
+    fn err_hex_decode_invalid() {
+        assert!(hex_decode("abc").is_err());
+        assert!(hex_decode("zz").is_err());
+    }
+}
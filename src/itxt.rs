@@ -0,0 +1,160 @@
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+
+use crate::{chunk::Chunk, chunk_type::ChunkType, result::*};
+
+/// The content of an `iTXt` chunk, laid out as
+/// `keyword\0 compression_flag compression_method language_tag\0 translated_keyword\0 text`.
+#[derive(Debug, PartialEq)]
+pub struct Itxt {
+    keyword: String,
+    language_tag: String,
+    translated_keyword: String,
+    text: String,
+    compressed: bool,
+}
+
+impl Itxt {
+    pub fn keyword(&self) -> &str {
+        &self.keyword
+    }
+
+    pub fn language_tag(&self) -> &str {
+        &self.language_tag
+    }
+
+    pub fn translated_keyword(&self) -> &str {
+        &self.translated_keyword
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.compressed
+    }
+
+    pub fn from_chunk(chunk: &Chunk) -> Result<Self> {
+        if chunk.chunk_type().to_string() != "iTXt" {
+            Err("not an iTXt chunk")?
+        }
+
+        let mut fields = chunk.data().splitn(2, |b| *b == 0);
+        let keyword = String::from_utf8(fields.next().unwrap().to_owned())?;
+        let rest = fields.next().ok_or("truncated iTXt chunk")?;
+
+        let (compressed, rest) = match rest {
+            [0, _, rest @ ..] => (false, rest),
+            [1, 0, rest @ ..] => (true, rest),
+            [1, _, ..] => Err("unknown iTXt compression method")?,
+            _ => Err("invalid iTXt compression flag")?,
+        };
+
+        let mut fields = rest.splitn(3, |b| *b == 0);
+        let language_tag = String::from_utf8(fields.next().unwrap().to_owned())?;
+        let translated_keyword = fields.next().ok_or("truncated iTXt chunk")?;
+        let translated_keyword = String::from_utf8(translated_keyword.to_owned())?;
+        let text = fields.next().ok_or("truncated iTXt chunk")?;
+
+        let text = if compressed {
+            let mut decompressed = Vec::new();
+            ZlibDecoder::new(text).read_to_end(&mut decompressed)?;
+            String::from_utf8(decompressed)?
+        } else {
+            String::from_utf8(text.to_owned())?
+        };
+
+        Ok(Self {
+            keyword,
+            language_tag,
+            translated_keyword,
+            text,
+            compressed,
+        })
+    }
+}
+
+pub fn itxt_chunk(
+    keyword: &str,
+    language_tag: &str,
+    translated_keyword: &str,
+    text: &str,
+    compressed: bool,
+) -> Result<Chunk> {
+    if keyword.is_empty() || keyword.len() > 79 {
+        Err("iTXt keyword should have 1 to 79 bytes")?
+    }
+    if [keyword, language_tag, translated_keyword]
+        .iter()
+        .any(|field| field.contains('\0'))
+    {
+        Err("iTXt fields cannot contain null characters")?
+    }
+
+    let text = if compressed {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(text.as_bytes())?;
+        encoder.finish()?
+    } else {
+        text.as_bytes().to_owned()
+    };
+
+    let data = [
+        keyword.as_bytes(),
+        &[0, compressed as u8, 0],
+        language_tag.as_bytes(),
+        &[0],
+        translated_keyword.as_bytes(),
+        &[0],
+        &text,
+    ]
+    .concat();
+
+    Ok(Chunk::new(ChunkType::from_str("iTXt")?, data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(compressed: bool) {
+        let text = "隱藏的訊息 — ein geheimer Text ✓";
+
+        let chunk = itxt_chunk("Comment", "zh-TW", "註解", text, compressed).unwrap();
+        let itxt = Itxt::from_chunk(&chunk).unwrap();
+
+        assert_eq!(itxt.keyword(), "Comment");
+        assert_eq!(itxt.language_tag(), "zh-TW");
+        assert_eq!(itxt.translated_keyword(), "註解");
+        assert_eq!(itxt.text(), text);
+        assert_eq!(itxt.is_compressed(), compressed);
+    }
+
+    #[test]
+    fn round_trip_uncompressed() {
+        round_trip(false);
+    }
+
+    #[test]
+    fn round_trip_compressed() {
+        round_trip(true);
+    }
+
+    #[test]
+    fn err_itxt_chunk_with_empty_keyword() {
+        assert!(itxt_chunk("", "en", "", "text", false).is_err());
+    }
+
+    #[test]
+    fn err_from_chunk_of_other_type() {
+        let chunk = Chunk::new(
+            ChunkType::from_str("tEXt").unwrap(),
+            b"Comment\0text".into(),
+        );
+
+        assert!(Itxt::from_chunk(&chunk).is_err());
+    }
+}
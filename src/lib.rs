@@ -1,9 +1,22 @@
 mod chunk;
+mod chunk_reader;
+mod chunk_ref;
 mod chunk_type;
+mod hex;
+mod itxt;
 mod png;
+mod png_builder;
+mod png_parser;
 mod result;
+mod secret;
 
 pub use chunk::*;
+pub use chunk_reader::*;
+pub use chunk_ref::*;
 pub use chunk_type::*;
+pub use itxt::*;
 pub use png::*;
+pub use png_builder::*;
+pub use png_parser::*;
 pub use result::*;
+pub use secret::*;
@@ -1,36 +1,87 @@
 mod args;
 mod commands;
+// The library keeps its hex helpers private, the binary compiles its own copy.
+#[path = "hex.rs"]
+mod hex;
+mod progress;
+
+use std::process::ExitCode;
 
 use clap::Parser;
 
 use pngsecret::Result;
 
 use args::{Args, Commands};
-use commands::{decode, encode, print, remove};
-
-fn main() -> Result<()> {
-    let args = Args::parse();
-
-    match args.command {
-        Commands::Encode {
-            file_path,
-            chunk_type,
-            message,
-            output_file,
-        } => encode(file_path, chunk_type, message, output_file)?,
+use commands::{
+    check_watermark, clean, decode, diff, encode, encode_hash, encode_many, entropy, error_json,
+    find, minify, move_chunk, pipe, print, remove, rename_type, stats, suggest_type,
+    usage_error_json, verify_hash, watermark,
+};
 
-        Commands::Decode {
-            file_path,
-            chunk_type,
-        } => decode(file_path, chunk_type)?,
+fn main() -> ExitCode {
+    let args = match Args::try_parse() {
+        Ok(args) => args,
+        // help and version are not errors, they go to stdout as usual
+        Err(error) if error.use_stderr() && wants_json_errors() => {
+            eprintln!("{}", usage_error_json(&error));
+            return ExitCode::from(2);
+        }
+        Err(error) => error.exit(),
+    };
+    let json_errors = args.json_errors;
+    match run(args) {
+        Ok(code) => code,
+        Err(error) => {
+            if json_errors {
+                eprintln!("{}", error_json(&error));
+            } else {
+                eprintln!("pngsecret: {}", error);
+            }
+            ExitCode::from(2)
+        }
+    }
+}
 
-        Commands::Remove {
-            file_path,
-            chunk_type,
-            output_file,
-        } => remove(file_path, chunk_type, output_file)?,
+/// Whether `--json-errors` was given, looked up by hand when the arguments do not parse.
+fn wants_json_errors() -> bool {
+    std::env::args_os()
+        .take_while(|arg| arg != "--")
+        .any(|arg| arg == "--json-errors")
+}
 
-        Commands::Print { file_path } => print(file_path)?,
+fn run(args: Args) -> Result<ExitCode> {
+    match args.command {
+        Commands::Encode(encode_args) => encode(encode_args, args.quiet)?,
+        Commands::EncodeMany(encode_many_args) => encode_many(encode_many_args, args.quiet)?,
+        Commands::Decode(decode_args) => decode(decode_args)?,
+        Commands::Remove(remove_args) => remove(remove_args, args.quiet)?,
+        Commands::MoveChunk(move_chunk_args) => move_chunk(move_chunk_args, args.quiet)?,
+        Commands::RenameType(rename_type_args) => rename_type(rename_type_args, args.quiet)?,
+        Commands::Clean(clean_args) => clean(clean_args, args.quiet)?,
+        Commands::Minify(minify_args) => minify(minify_args, args.quiet)?,
+        Commands::Print(print_args) => print(print_args, !args.quiet)?,
+        Commands::SuggestType(suggest_type_args) => suggest_type(suggest_type_args)?,
+        Commands::Entropy(entropy_args) => entropy(entropy_args, !args.quiet)?,
+        Commands::Stats(stats_args) => stats(stats_args)?,
+        Commands::Find(find_args) => find(find_args)?,
+        Commands::Watermark(watermark_args) => watermark(watermark_args, args.quiet)?,
+        Commands::CheckWatermark(check_watermark_args) => {
+            if !check_watermark(check_watermark_args)? {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Commands::EncodeHash(encode_hash_args) => encode_hash(encode_hash_args, args.quiet)?,
+        Commands::VerifyHash(verify_hash_args) => {
+            if !verify_hash(verify_hash_args)? {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Commands::Diff(diff_args) => {
+            if !diff(diff_args)? {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Commands::Pipe(pipe_args) => pipe(pipe_args)?,
     }
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
@@ -1,49 +1,344 @@
-use crate::{chunk::Chunk, result::*};
+use std::path::Path;
 
+use crate::{
+    chunk::Chunk, chunk_ref::ChunkRef, chunk_type::ChunkType, png_parser::PngParser, result::*,
+};
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Png {
     chunks: Vec<Chunk>,
+    trailing: Vec<u8>,
 }
 
 impl Png {
     pub const STANDARD_HEADER: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
 
     pub fn from_chunks(chunks: Vec<Chunk>) -> Self {
-        Self { chunks }
+        Self {
+            chunks,
+            trailing: vec![],
+        }
+    }
+
+    /// An empty png with room for `capacity` chunks, so that appending
+    /// that many chunks does not reallocate.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_chunks(Vec::with_capacity(capacity))
+    }
+
+    /// Makes room for `additional` more chunks. Appending one chunk at a time
+    /// is amortized constant time anyway, this only saves the reallocations.
+    pub fn reserve(&mut self, additional: usize) {
+        self.chunks.reserve(additional)
+    }
+
+    /// Reads and parses the file, also returning its raw bytes.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<(Self, Vec<u8>)> {
+        let path = path.as_ref();
+        let bytes =
+            std::fs::read(path).map_err(|source| Error::io(&path.to_string_lossy(), source))?;
+        let png = Self::try_from(&bytes[..])?;
+
+        Ok((png, bytes))
+    }
+
+    pub fn try_from_with_limits(bytes: &[u8], max_chunk_data: Option<usize>) -> Result<Self> {
+        PngParser::new().max_chunk_data(max_chunk_data).parse(bytes)
+    }
+
+    /// Iterates the chunks mutably, e.g. to change their data in place.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Chunk> {
+        self.chunks.iter_mut()
+    }
+
+    /// Parses the png without checking the crc of its chunks,
+    /// one can find the corrupted chunks by `Chunk::verify_crc`.
+    /// The stored crcs are kept, so `as_bytes` reproduces the input byte for byte.
+    /// Bytes after IEND which are not chunks are kept as `trailing_data`.
+    pub fn try_from_lenient(bytes: &[u8], max_chunk_data: Option<usize>) -> Result<Self> {
+        PngParser::new()
+            .check_crc(false)
+            .strict_trailing(false)
+            .max_chunk_data(max_chunk_data)
+            .parse(bytes)
+    }
+
+    pub(crate) fn parse(
+        bytes: &[u8],
+        max_chunk_data: Option<usize>,
+        check_crc: bool,
+        strict_trailing: bool,
+    ) -> Result<Self> {
+        let length = bytes.len();
+
+        if length < 8 {
+            Err("wrong length for png file")?
+        }
+        let header: [u8; 8] = bytes[..8].to_vec().try_into().unwrap();
+        if header != Self::STANDARD_HEADER {
+            Err("wrong header")?
+        }
+
+        let mut png = Self::from_chunks(vec![]);
+        let mut next_index = 8;
+        while next_index < length {
+            let rest = &bytes[next_index..];
+            match Self::parse_chunk(rest, max_chunk_data, check_crc) {
+                Ok(chunk) => {
+                    next_index += chunk.total_len();
+                    png.chunks.push(chunk);
+                }
+                Err(_) if !strict_trailing && png.contains("IEND") => {
+                    png.trailing = rest.to_vec();
+                    break;
+                }
+                Err(error) => Err(error)?,
+            }
+        }
+
+        Ok(png)
+    }
+
+    fn parse_chunk(bytes: &[u8], max_chunk_data: Option<usize>, check_crc: bool) -> Result<Chunk> {
+        let length = bytes.len();
+
+        if length < 4 {
+            Err("wrong length for png file")?
+        }
+        let data_length: [u8; 4] = bytes[..4].to_vec().try_into().unwrap();
+        let data_length = u32::from_be_bytes(data_length);
+        let data_length = usize::try_from(data_length)?;
+
+        if max_chunk_data.is_some_and(|max| data_length > max) {
+            Err("chunk data exceeds limit")?
+        }
+
+        if 12 + data_length > length {
+            Err("wrong length for png file")?
+        }
+        let bytes = &bytes[..12 + data_length];
+
+        Ok(ChunkRef::parse(bytes, check_crc)?.into_chunk())
     }
 
     pub fn chunks(&self) -> &[Chunk] {
         &self.chunks
     }
 
+    /// Bytes after IEND which are not chunks, only kept by `try_from_lenient`.
+    pub fn trailing_data(&self) -> &[u8] {
+        &self.trailing
+    }
+
+    pub fn chunk_at(&self, index: usize) -> Option<&Chunk> {
+        self.chunks.get(index)
+    }
+
     pub fn chunk_by_type(&self, chunk_type: &str) -> Option<&Chunk> {
         self.chunks
             .iter()
             .find(|chunk| chunk.chunk_type().to_string() == chunk_type)
     }
 
+    pub fn chunk_by_type_mut(&mut self, chunk_type: &str) -> Option<&mut Chunk> {
+        self.chunks
+            .iter_mut()
+            .find(|chunk| chunk.chunk_type().to_string() == chunk_type)
+    }
+
+    pub fn contains(&self, chunk_type: &str) -> bool {
+        self.chunk_by_type(chunk_type).is_some()
+    }
+
+    /// The type of each chunk in order, duplicates included.
+    pub fn chunk_types(&self) -> Vec<ChunkType> {
+        self.chunks
+            .iter()
+            .map(|chunk| chunk.chunk_type().clone())
+            .collect()
+    }
+
+    pub fn chunks_by_type<'a>(&'a self, chunk_type: &'a str) -> impl Iterator<Item = &'a Chunk> {
+        self.chunks
+            .iter()
+            .filter(move |chunk| chunk.chunk_type().to_string() == chunk_type)
+    }
+
+    pub fn chunks_by_type_ignore_case<'a>(
+        &'a self,
+        chunk_type: &'a str,
+    ) -> impl Iterator<Item = &'a Chunk> {
+        self.chunks.iter().filter(move |chunk| {
+            chunk
+                .chunk_type()
+                .to_string()
+                .eq_ignore_ascii_case(chunk_type)
+        })
+    }
+
+    pub fn chunk_count_by_type(&self, chunk_type: &str) -> usize {
+        self.chunks_by_type(chunk_type).count()
+    }
+
+    /// The zlib image datastream split across the IDAT chunks.
+    pub fn idat_stream(&self) -> Vec<u8> {
+        self.chunks_by_type("IDAT")
+            .flat_map(|chunk| chunk.data())
+            .copied()
+            .collect()
+    }
+
     pub fn append_chunk(&mut self, chunk: Chunk) {
         self.chunks.push(chunk)
     }
 
+    pub fn append_chunks<I: IntoIterator<Item = Chunk>>(&mut self, chunks: I) {
+        self.chunks.extend(chunks)
+    }
+
     pub fn remove_chunk(&mut self, chunk_type: &str) -> Result<Chunk> {
+        self.remove_chunk_matching(chunk_type, |_| true)
+    }
+
+    /// Removes the first chunk of the given type satisfying the predicate.
+    pub fn remove_chunk_matching<F: Fn(&Chunk) -> bool>(
+        &mut self,
+        chunk_type: &str,
+        predicate: F,
+    ) -> Result<Chunk> {
         let index = self
             .chunks
             .iter()
-            .position(|chunk| chunk.chunk_type().to_string() == chunk_type)
-            .ok_or("no chunk with such type")?;
+            .position(|chunk| chunk.chunk_type().to_string() == chunk_type && predicate(chunk))
+            .ok_or_else(|| Error::ChunkNotFound(chunk_type.to_string()))?;
 
         Ok(self.chunks.remove(index))
     }
 
+    pub fn move_chunk(&mut self, chunk_type: &str, to_index: usize) -> Result<()> {
+        if to_index >= self.chunks.len() {
+            Err("index out of range")?
+        }
+
+        let chunk = self.remove_chunk(chunk_type)?;
+        self.chunks.insert(to_index, chunk);
+
+        Ok(())
+    }
+
+    pub fn normalize_order(&mut self) {
+        let first_idat = self
+            .chunks
+            .iter()
+            .position(|chunk| chunk.chunk_type().to_string() == "IDAT")
+            .unwrap_or(self.chunks.len());
+
+        let mut ranked: Vec<(usize, Chunk)> = self
+            .chunks
+            .drain(..)
+            .enumerate()
+            .map(|(index, chunk)| {
+                let rank = match chunk.chunk_type().to_string().as_str() {
+                    "IHDR" => 0,
+                    "cHRM" | "gAMA" | "iCCP" | "sBIT" | "sRGB" => 1,
+                    "PLTE" => 2,
+                    "bKGD" | "hIST" | "tRNS" | "pHYs" | "sPLT" => 3,
+                    "IDAT" => 5,
+                    "IEND" => 7,
+                    _ if index < first_idat => 4,
+                    _ => 6,
+                };
+                (rank, chunk)
+            })
+            .collect();
+
+        ranked.sort_by_key(|(rank, _)| *rank);
+        self.chunks = ranked.into_iter().map(|(_, chunk)| chunk).collect();
+    }
+
+    pub fn clear_trailing_data(&mut self) {
+        self.trailing.clear()
+    }
+
+    /// Keeps only the chunks satisfying the predicate, in order.
+    pub fn retain<F: FnMut(&Chunk) -> bool>(&mut self, f: F) {
+        self.chunks.retain(f)
+    }
+
+    /// Drops the chunks after the first IEND and returns how many were dropped.
+    pub fn sanitize(&mut self) -> usize {
+        let end = self
+            .chunks
+            .iter()
+            .position(|chunk| chunk.chunk_type().to_string() == "IEND")
+            .map_or(self.chunks.len(), |i| i + 1);
+
+        self.chunks.drain(end..).count()
+    }
+
+    /// Ancillary, private, safe-to-copy chunk types which are not used in this png yet.
+    pub fn unused_safe_chunk_types(&self, count: usize) -> Vec<ChunkType> {
+        let preferred: Vec<[u8; 4]> = ["ruSt", "seCr", "hiDe", "mrKs", "noTe"]
+            .iter()
+            .map(|s| s.as_bytes().try_into().unwrap())
+            .collect();
+        let others = (0..26u32.pow(4))
+            .map(|n| {
+                let letter = |i: u32| b'a' + (n / 26u32.pow(i) % 26) as u8;
+                [
+                    letter(3),
+                    letter(2),
+                    letter(1).to_ascii_uppercase(),
+                    letter(0),
+                ]
+            })
+            .filter(|bytes| !preferred.contains(bytes));
+
+        preferred
+            .iter()
+            .copied()
+            .chain(others)
+            .map(|bytes: [u8; 4]| ChunkType::try_from(bytes).unwrap())
+            .filter(|chunk_type| {
+                self.chunks
+                    .iter()
+                    .all(|chunk| chunk.chunk_type() != chunk_type)
+            })
+            .take(count)
+            .collect()
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if let Some(chunk) = self
+            .chunks
+            .iter()
+            .find(|chunk| !chunk.chunk_type().is_valid())
+        {
+            Err(format!("invalid chunk type {}", chunk.chunk_type()))?
+        }
+
+        match self.chunks.first() {
+            Some(chunk) if chunk.chunk_type().to_string() == "IHDR" => {}
+            _ => Err("first chunk should be IHDR")?,
+        }
+
+        if !self.contains("IEND") {
+            Err("no IEND chunk")?
+        }
+
+        Ok(())
+    }
+
     pub fn as_bytes(&self) -> Vec<u8> {
         let chunks: &Vec<u8> = &self.chunks[..]
-            .into_iter()
+            .iter()
             .flat_map(|chunk| chunk.as_bytes())
             .collect();
 
         Self::STANDARD_HEADER
             .iter()
             .chain(chunks)
+            .chain(&self.trailing)
             .copied()
             .collect()
     }
@@ -53,41 +348,21 @@ impl TryFrom<&[u8]> for Png {
     type Error = Error;
 
     fn try_from(bytes: &[u8]) -> Result<Self> {
-        let length = bytes.len();
-
-        if length < 8 {
-            Err("wrong length for png file")?
-        }
-        let header: [u8; 8] = bytes[..8].to_vec().try_into().unwrap();
-        if header != Self::STANDARD_HEADER {
-            Err("wrong header")?
-        }
+        PngParser::new().parse(bytes)
+    }
+}
 
-        let mut chunks = Vec::<Chunk>::new();
-        let mut next_index = 8;
-        while next_index < length {
-            if next_index + 4 > length {
-                Err("wrong length for png file")?
-            }
-            let data_length: [u8; 4] = bytes[next_index..next_index + 4]
-                .to_vec()
-                .try_into()
-                .unwrap();
-            let data_length = u32::from_be_bytes(data_length);
-            let data_length = usize::try_from(data_length)?;
-
-            if next_index + 12 + data_length > length {
-                Err("wrong length for png file")?
-            }
-            let bytes = &bytes[next_index..next_index + 12 + data_length];
+impl From<Png> for Vec<u8> {
+    fn from(png: Png) -> Self {
+        png.as_bytes()
+    }
+}
 
-            let chunk = Chunk::try_from(bytes)?;
-            chunks.push(chunk);
+impl std::ops::Index<usize> for Png {
+    type Output = Chunk;
 
-            next_index += 12 + data_length;
-        }
-
-        Ok(Self { chunks })
+    fn index(&self, index: usize) -> &Chunk {
+        &self.chunks[index]
     }
 }
 
@@ -99,7 +374,7 @@ impl std::fmt::Display for Png {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use crate::result::Result;
     use std::str::FromStr;
@@ -113,13 +388,11 @@ mod tests {
     }
 
     fn testing_chunks() -> Vec<Chunk> {
-        let mut chunks = Vec::<Chunk>::new();
-
-        chunks.push(chunk_from_string("FRST", "first chunk").unwrap());
-        chunks.push(chunk_from_string("miDl", "middle chunk").unwrap());
-        chunks.push(chunk_from_string("IEND", "end chunk").unwrap());
-
-        chunks
+        vec![
+            chunk_from_string("FRST", "first chunk").unwrap(),
+            chunk_from_string("miDl", "middle chunk").unwrap(),
+            chunk_from_string("IEND", "end chunk").unwrap(),
+        ]
     }
 
     fn testing_png_bytes(header: [u8; 8], chunks: Vec<Chunk>) -> Vec<u8> {
@@ -144,6 +417,24 @@ mod tests {
         assert_eq!(png.chunks().len(), 3);
     }
 
+    #[test]
+    fn open_returns_png_and_raw_bytes() {
+        let path = std::env::temp_dir().join(format!("pngsecret-open-{}.png", std::process::id()));
+        std::fs::write(&path, PNG_FILE).unwrap();
+
+        let (png, bytes) = Png::open(&path).unwrap();
+
+        assert_eq!(bytes, PNG_FILE);
+        assert_eq!(bytes, png.as_bytes());
+    }
+
+    #[test]
+    fn err_open_missing_file() {
+        let result = Png::open("no/such/file.png");
+
+        assert!(matches!(result, Err(Error::Io { path: Some(_), .. })));
+    }
+
     #[test]
     fn ok_try_from_valid_bytes() {
         let bytes = testing_png_bytes(Png::STANDARD_HEADER, testing_chunks());
@@ -175,6 +466,78 @@ mod tests {
         assert!(png.is_err());
     }
 
+    #[test]
+    fn try_from_with_limits() {
+        let chunk = chunk_from_string("teSt", "0123456789").unwrap();
+        let bytes = testing_png_bytes(Png::STANDARD_HEADER, vec![chunk]);
+
+        assert!(Png::try_from_with_limits(&bytes, Some(10)).is_ok());
+        assert!(Png::try_from_with_limits(&bytes, Some(9)).is_err());
+        assert!(Png::try_from_with_limits(&bytes, None).is_ok());
+    }
+
+    #[test]
+    fn err_try_from_with_limits_before_reading_data() {
+        let bytes: Vec<u8> = Png::STANDARD_HEADER
+            .iter()
+            .chain(&u32::MAX.to_be_bytes())
+            .copied()
+            .collect();
+
+        let result = Png::try_from_with_limits(&bytes, Some(1024));
+
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "chunk data exceeds limit"
+        );
+    }
+
+    #[test]
+    fn try_from_lenient_with_wrong_crc() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[32] ^= 0xff;
+
+        let png = Png::try_from_lenient(&bytes, None).unwrap();
+
+        assert!(Png::try_from(&bytes[..]).is_err());
+        assert!(!png.chunks()[0].verify_crc());
+        assert!(png.chunks()[1].verify_crc());
+        assert_eq!(png.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn try_from_lenient_keeps_trailing_data() {
+        let bytes: Vec<u8> = PNG_FILE.iter().chain(b"thumbnail").copied().collect();
+
+        let png = Png::try_from_lenient(&bytes, None).unwrap();
+
+        assert!(Png::try_from(&bytes[..]).is_err());
+        assert_eq!(png.chunks().len(), 3);
+        assert_eq!(png.trailing_data(), b"thumbnail");
+        assert_eq!(png.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn err_try_from_lenient_with_garbage_before_iend() {
+        let bytes: Vec<u8> = PNG_FILE[..PNG_FILE.len() - 12]
+            .iter()
+            .chain(b"thumbnail")
+            .copied()
+            .collect();
+
+        assert!(Png::try_from_lenient(&bytes, None).is_err());
+    }
+
+    #[test]
+    fn into_bytes() {
+        let png = testing_png();
+        let expected = png.as_bytes();
+
+        let bytes: Vec<u8> = png.into();
+
+        assert_eq!(bytes, expected);
+    }
+
     #[test]
     fn list_chunks() {
         let png = testing_png();
@@ -182,6 +545,29 @@ mod tests {
         assert_eq!(chunks.len(), 3);
     }
 
+    #[test]
+    fn chunk_at() {
+        let png = testing_png();
+
+        assert_eq!(png.chunk_at(0).unwrap().chunk_type().to_string(), "FRST");
+        assert!(png.chunk_at(99).is_none());
+    }
+
+    #[test]
+    fn index() {
+        let png = testing_png();
+
+        assert_eq!(&png[0], png.chunk_at(0).unwrap());
+    }
+
+    #[test]
+    #[should_panic]
+    fn index_out_of_range() {
+        let png = testing_png();
+
+        let _ = &png[99];
+    }
+
     #[test]
     fn chunk_by_type() {
         let png = testing_png();
@@ -191,6 +577,36 @@ mod tests {
         assert_eq!(chunk.data_as_string().unwrap(), "first chunk");
     }
 
+    #[test]
+    fn contains() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+
+        assert!(png.contains("IEND"));
+        assert!(!png.contains("teSt"));
+    }
+
+    #[test]
+    fn chunk_count_by_type() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(chunk_from_string("teSt", "first").unwrap());
+        png.append_chunk(chunk_from_string("teSt", "second").unwrap());
+
+        assert_eq!(png.chunk_count_by_type("IDAT"), 1);
+        assert_eq!(png.chunk_count_by_type("teSt"), 2);
+        assert_eq!(png.chunk_count_by_type("ruSt"), 0);
+    }
+
+    #[test]
+    fn chunks_by_type_ignore_case() {
+        let png = testing_png();
+
+        let chunks: Vec<&Chunk> = png.chunks_by_type_ignore_case("midl").collect();
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].chunk_type().to_string(), "miDl");
+        assert!(png.chunk_by_type("midl").is_none());
+    }
+
     #[test]
     fn append_chunk() {
         let mut png = testing_png();
@@ -213,6 +629,240 @@ mod tests {
         assert!(chunk.is_none());
     }
 
+    #[test]
+    fn move_chunk() {
+        let mut png = testing_png();
+        png.move_chunk("IEND", 0).unwrap();
+
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+
+        assert_eq!(types, ["IEND", "FRST", "miDl"]);
+    }
+
+    #[test]
+    fn err_move_chunk_out_of_range() {
+        let mut png = testing_png();
+        let result = png.move_chunk("IEND", 3);
+
+        assert!(result.is_err());
+        assert_eq!(png.chunks().len(), 3);
+    }
+
+    #[test]
+    fn chunk_types_of_image_file() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+
+        let expected: Vec<ChunkType> = ["IHDR", "IDAT", "IEND"]
+            .into_iter()
+            .map(|s| ChunkType::from_str(s).unwrap())
+            .collect();
+        assert_eq!(png.chunk_types(), expected);
+    }
+
+    #[test]
+    fn idat_stream_joins_idat_chunks() {
+        let png = Png::from_chunks(vec![
+            chunk_from_string("IHDR", "header").unwrap(),
+            chunk_from_string("IDAT", "first").unwrap(),
+            chunk_from_string("ruSt", "between").unwrap(),
+            chunk_from_string("IDAT", "second").unwrap(),
+            chunk_from_string("IEND", "").unwrap(),
+        ]);
+
+        assert_eq!(png.chunks_by_type("IDAT").count(), 2);
+        assert_eq!(png.idat_stream(), b"firstsecond");
+    }
+
+    #[test]
+    fn clone_is_independent() {
+        let png = testing_png();
+        let mut cloned = png.clone();
+
+        cloned.append_chunk(chunk_from_string("ruSt", "cloned").unwrap());
+
+        assert_eq!(png.chunks().len(), 3);
+        assert_eq!(cloned.chunks().len(), 4);
+        assert_ne!(png, cloned);
+    }
+
+    #[test]
+    fn with_capacity_and_append_chunks() {
+        let mut png = Png::with_capacity(1000);
+        png.append_chunk(chunk_from_string("IHDR", "header").unwrap());
+        png.reserve(999);
+
+        png.append_chunks((0..998).map(|i| chunk_from_string("ruSt", &i.to_string()).unwrap()));
+        png.append_chunk(chunk_from_string("IEND", "").unwrap());
+
+        assert_eq!(png.chunks().len(), 1000);
+        assert_eq!(Png::try_from(&png.as_bytes()[..]).unwrap(), png);
+    }
+
+    #[test]
+    fn append_chunks() {
+        let mut png = testing_png();
+
+        png.append_chunks(["ruSt", "teSt", "ruSt"].map(|s| chunk_from_string(s, s).unwrap()));
+
+        assert_eq!(png.chunks().len(), 6);
+        assert_eq!(png.chunk_count_by_type("ruSt"), 2);
+    }
+
+    #[test]
+    fn retain_critical_chunks() {
+        let mut png = testing_png();
+
+        png.retain(|chunk| chunk.chunk_type().is_critical());
+
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(types, ["FRST", "IEND"]);
+    }
+
+    #[test]
+    fn iter_mut_clears_ancillary_data() {
+        let mut png = testing_png();
+        let critical: Vec<Chunk> = png
+            .chunks()
+            .iter()
+            .filter(|chunk| chunk.chunk_type().is_critical())
+            .cloned()
+            .collect();
+
+        png.iter_mut()
+            .filter(|chunk| !chunk.chunk_type().is_critical())
+            .for_each(|chunk| chunk.set_data(vec![]));
+
+        for chunk in png.chunks() {
+            if !chunk.chunk_type().is_critical() {
+                assert!(chunk.data().is_empty());
+                assert!(chunk.verify_crc());
+            }
+        }
+        let after: Vec<&Chunk> = png
+            .chunks()
+            .iter()
+            .filter(|chunk| chunk.chunk_type().is_critical())
+            .collect();
+        assert_eq!(after, critical.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn sanitize_drops_chunks_after_iend() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_string("ruSt", "trailing").unwrap());
+        png.append_chunk(chunk_from_string("ruSt", "more trailing").unwrap());
+
+        assert_eq!(png.sanitize(), 2);
+        assert_eq!(png.chunks().len(), 3);
+        assert_eq!(
+            png.chunks().last().unwrap().chunk_type().to_string(),
+            "IEND"
+        );
+    }
+
+    #[test]
+    fn normalize_order() {
+        let mut png = Png::from_chunks(vec![
+            chunk_from_string("IEND", "").unwrap(),
+            chunk_from_string("IDAT", "first data").unwrap(),
+            chunk_from_string("teSt", "test data").unwrap(),
+            chunk_from_string("IDAT", "second data").unwrap(),
+            chunk_from_string("PLTE", "palette").unwrap(),
+            chunk_from_string("gAMA", "gamma").unwrap(),
+            chunk_from_string("IHDR", "header").unwrap(),
+            chunk_from_string("ruSt", "trailing").unwrap(),
+        ]);
+
+        png.normalize_order();
+
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+        assert_eq!(
+            types,
+            ["IHDR", "gAMA", "PLTE", "IDAT", "IDAT", "teSt", "ruSt", "IEND"]
+        );
+        assert_eq!(png.chunks()[3].data_as_string().unwrap(), "first data");
+    }
+
+    #[test]
+    fn unused_safe_chunk_types() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(chunk_from_string("ruSt", "test data").unwrap());
+
+        let chunk_types = png.unused_safe_chunk_types(10);
+
+        assert_eq!(chunk_types.len(), 10);
+        for chunk_type in chunk_types {
+            assert!(chunk_type.is_ancillary());
+            assert!(chunk_type.is_private());
+            assert!(chunk_type.is_safe_to_copy());
+            assert!(chunk_type.is_valid());
+            assert!(png.chunk_by_type(&chunk_type.to_string()).is_none());
+        }
+    }
+
+    #[test]
+    fn unused_safe_chunk_types_without_duplicates() {
+        let png = testing_png();
+
+        let chunk_types = png.unused_safe_chunk_types(usize::MAX);
+
+        let unique: std::collections::HashSet<String> = chunk_types
+            .iter()
+            .map(|chunk_type| chunk_type.to_string())
+            .collect();
+        assert_eq!(unique.len(), chunk_types.len());
+        assert_eq!(chunk_types.len(), 26usize.pow(4) - 1);
+    }
+
+    #[test]
+    fn ok_validate_image_file() {
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+
+        assert!(png.validate().is_ok());
+    }
+
+    #[test]
+    fn err_validate_without_ihdr_first() {
+        let png = testing_png();
+
+        assert!(png.validate().is_err());
+    }
+
+    #[test]
+    fn err_validate_invalid_chunk_type() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        png.append_chunk(chunk_from_string("RUSt", "test data").unwrap());
+
+        assert!(png.validate().is_err());
+    }
+
+    #[test]
+    fn remove_chunk_matching() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_string("TeST", "keep me").unwrap());
+        png.append_chunk(chunk_from_string("TeST", "remove me").unwrap());
+
+        let chunk = png
+            .remove_chunk_matching("TeST", |chunk| chunk.data().starts_with(b"remove"))
+            .unwrap();
+
+        assert_eq!(chunk.data_as_string().unwrap(), "remove me");
+        assert_eq!(png.chunk_count_by_type("TeST"), 1);
+        assert!(png.remove_chunk_matching("TeST", |_| false).is_err());
+    }
+
     #[test]
     fn png_fromimage_file() {
         let png = Png::try_from(&PNG_FILE[..]);
@@ -227,6 +877,23 @@ mod tests {
         assert_eq!(actual, expcted);
     }
 
+    #[test]
+    fn eq_after_round_trip() {
+        let png = testing_png();
+        let round_trip = Png::try_from(&png.as_bytes()[..]).unwrap();
+
+        assert_eq!(png, round_trip);
+    }
+
+    #[test]
+    fn ne_with_different_chunks() {
+        let png = testing_png();
+        let mut other = testing_png();
+        other.append_chunk(chunk_from_string("teSt", "test data").unwrap());
+
+        assert_ne!(png, other);
+    }
+
     #[test]
     fn test_png_trait_impls() {
         let chunk_bytes: Vec<u8> = testing_chunks()
@@ -245,12 +912,5 @@ mod tests {
         let _png_string = format!("{}", png);
     }
 
-    const PNG_FILE: [u8; 88] = [
-        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44,
-        0x52, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x02, 0x08, 0x02, 0x00, 0x00, 0x00, 0x12,
-        0x16, 0xf1, 0x4d, 0x00, 0x00, 0x00, 0x1f, 0x49, 0x44, 0x41, 0x54, 0x08, 0x1d, 0x01, 0x14,
-        0x00, 0xeb, 0xff, 0x00, 0xff, 0x00, 0x00, 0x00, 0xff, 0x00, 0x00, 0x00, 0xff, 0x00, 0x00,
-        0x00, 0x00, 0x80, 0x80, 0x80, 0xff, 0xff, 0xff, 0x3a, 0x61, 0x07, 0x7b, 0xcb, 0xca, 0x5c,
-        0x63, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
-    ];
+    pub(crate) const PNG_FILE: [u8; 88] = *include_bytes!("../tests/data/image.png");
 }
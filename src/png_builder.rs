@@ -0,0 +1,108 @@
+use std::str::FromStr;
+
+use crate::{chunk::Chunk, chunk_type::ChunkType, png::Png, result::*};
+
+/// Builds a png with `IHDR` first and `IEND` last.
+#[derive(Default)]
+pub struct PngBuilder {
+    ihdr: Option<Chunk>,
+    chunks: Vec<Chunk>,
+    error: Option<Error>,
+}
+
+impl PngBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ihdr(mut self, width: u32, height: u32, bit_depth: u8, color_type: u8) -> Self {
+        let data = width
+            .to_be_bytes()
+            .into_iter()
+            .chain(height.to_be_bytes())
+            .chain([bit_depth, color_type, 0, 0, 0])
+            .collect();
+        self.ihdr = Some(Chunk::new(ChunkType::from_str("IHDR").unwrap(), data));
+        self
+    }
+
+    pub fn add_chunk(mut self, chunk: Chunk) -> Self {
+        self.chunks.push(chunk);
+        self
+    }
+
+    pub fn text(mut self, keyword: &str, value: &str) -> Self {
+        if keyword.is_empty() || keyword.len() > 79 || keyword.contains('\0') {
+            self.error = Some(format!("invalid tEXt keyword '{}'", keyword).into());
+            return self;
+        }
+
+        let data = [keyword.as_bytes(), &[0], value.as_bytes()].concat();
+        self.add_chunk(Chunk::new(ChunkType::from_str("tEXt").unwrap(), data))
+    }
+
+    pub fn build(self) -> Result<Png> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        let mut chunks: Vec<Chunk> = self.ihdr.into_iter().chain(self.chunks).collect();
+
+        let is_iend = |chunk: &Chunk| chunk.chunk_type().to_string() == "IEND";
+        if !chunks.last().is_some_and(is_iend) {
+            if chunks.iter().any(is_iend) {
+                Err("IEND should be the last chunk")?
+            }
+            chunks.push(Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![]));
+        }
+
+        let png = Png::from_chunks(chunks);
+        png.validate()?;
+        Ok(png)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idat() -> Chunk {
+        let data = vec![0x08, 0x1d, 0x01, 0x00, 0x00, 0xff, 0xff];
+        Chunk::new(ChunkType::from_str("IDAT").unwrap(), data)
+    }
+
+    #[test]
+    fn build_minimal_png() {
+        let png = PngBuilder::new()
+            .ihdr(1, 1, 8, 0)
+            .add_chunk(idat())
+            .text("Comment", "test data")
+            .build()
+            .unwrap();
+
+        let types: Vec<String> = png
+            .chunks()
+            .iter()
+            .map(|chunk| chunk.chunk_type().to_string())
+            .collect();
+
+        assert_eq!(types, ["IHDR", "IDAT", "tEXt", "IEND"]);
+        assert_eq!(png.chunks()[2].data(), b"Comment\0test data");
+        assert!(png.validate().is_ok());
+        assert!(Png::try_from(&png.as_bytes()[..]).is_ok());
+    }
+
+    #[test]
+    fn err_build_without_ihdr() {
+        let result = PngBuilder::new().add_chunk(idat()).build();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn err_build_with_invalid_keyword() {
+        let result = PngBuilder::new().ihdr(1, 1, 8, 0).text("", "test").build();
+
+        assert!(result.is_err());
+    }
+}
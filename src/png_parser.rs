@@ -0,0 +1,111 @@
+use crate::{png::Png, result::*};
+
+/// Parses pngs, checking crcs and rejecting bytes after IEND by default.
+#[derive(Clone, Copy)]
+pub struct PngParser {
+    check_crc: bool,
+    strict_trailing: bool,
+    max_chunk_data: Option<usize>,
+}
+
+impl Default for PngParser {
+    fn default() -> Self {
+        Self {
+            check_crc: true,
+            strict_trailing: true,
+            max_chunk_data: None,
+        }
+    }
+}
+
+impl PngParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether to reject chunks with a wrong crc, otherwise their stored crc is kept.
+    pub fn check_crc(mut self, check_crc: bool) -> Self {
+        self.check_crc = check_crc;
+        self
+    }
+
+    /// Whether to reject bytes after IEND which are not chunks,
+    /// otherwise they are kept as `Png::trailing_data`.
+    pub fn strict_trailing(mut self, strict_trailing: bool) -> Self {
+        self.strict_trailing = strict_trailing;
+        self
+    }
+
+    /// Rejects chunks declaring more data bytes than this.
+    pub fn max_chunk_data(mut self, max_chunk_data: Option<usize>) -> Self {
+        self.max_chunk_data = max_chunk_data;
+        self
+    }
+
+    pub fn parse(&self, bytes: &[u8]) -> Result<Png> {
+        Png::parse(
+            bytes,
+            self.max_chunk_data,
+            self.check_crc,
+            self.strict_trailing,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::png::tests::PNG_FILE;
+
+    #[test]
+    fn default_is_strict() {
+        let png = PngParser::new().parse(&PNG_FILE).unwrap();
+
+        assert_eq!(png, Png::try_from(&PNG_FILE[..]).unwrap());
+    }
+
+    #[test]
+    fn check_crc() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[32] ^= 0xff;
+
+        assert!(matches!(
+            PngParser::new().parse(&bytes),
+            Err(Error::CrcMismatch { .. })
+        ));
+
+        let png = PngParser::new().check_crc(false).parse(&bytes).unwrap();
+        assert!(!png.chunks()[0].verify_crc());
+        assert_eq!(png.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn strict_trailing() {
+        let bytes: Vec<u8> = PNG_FILE.iter().chain(b"thumbnail").copied().collect();
+
+        assert!(PngParser::new().parse(&bytes).is_err());
+
+        let png = PngParser::new()
+            .strict_trailing(false)
+            .parse(&bytes)
+            .unwrap();
+        assert_eq!(png.trailing_data(), b"thumbnail");
+        assert!(png.chunks().iter().all(|chunk| chunk.verify_crc()));
+    }
+
+    #[test]
+    fn max_chunk_data() {
+        assert!(PngParser::new()
+            .max_chunk_data(Some(31))
+            .parse(&PNG_FILE)
+            .is_ok());
+        assert_eq!(
+            PngParser::new()
+                .max_chunk_data(Some(30))
+                .parse(&PNG_FILE)
+                .unwrap_err()
+                .to_string(),
+            "chunk data exceeds limit"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use std::io::{Read, Write};
+
+pub struct Progress<W: Write> {
+    out: W,
+    enabled: bool,
+    total: usize,
+    done: usize,
+}
+
+impl<W: Write> Progress<W> {
+    pub fn new(out: W, enabled: bool, total: usize) -> Self {
+        Self {
+            out,
+            enabled,
+            total,
+            done: 0,
+        }
+    }
+
+    pub fn advance(&mut self, label: &str) {
+        self.done += 1;
+
+        if self.enabled {
+            let percent = self.done * 100 / self.total.max(1);
+            // progress is best-effort, a closed stderr should not abort the work
+            let _ = writeln!(
+                self.out,
+                "[{:>3}%] {}/{} {}",
+                percent, self.done, self.total, label
+            );
+        }
+    }
+
+    /// Advances by `amount` steps at once, e.g. bytes, only reporting when the
+    /// percentage changes.
+    pub fn advance_by(&mut self, amount: usize, label: &str) {
+        let total = self.total.max(1);
+        let before = self.done * 100 / total;
+        self.done += amount;
+        let percent = self.done * 100 / total;
+
+        if self.enabled && percent != before {
+            let _ = writeln!(
+                self.out,
+                "[{:>3}%] {}/{} {}",
+                percent, self.done, self.total, label
+            );
+        }
+    }
+}
+
+/// Reports the progress of reading through `inner`, in bytes.
+pub struct ProgressReader<R: Read, W: Write> {
+    inner: R,
+    progress: Progress<W>,
+    label: String,
+}
+
+impl<R: Read, W: Write> ProgressReader<R, W> {
+    pub fn new(inner: R, progress: Progress<W>, label: &str) -> Self {
+        Self {
+            inner,
+            progress,
+            label: label.to_string(),
+        }
+    }
+}
+
+impl<R: Read, W: Write> Read for ProgressReader<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.progress.advance_by(n, &self.label);
+        Ok(n)
+    }
+}
+
+/// Reports the progress of writing through `inner`, in bytes.
+pub struct ProgressWriter<W: Write, P: Write> {
+    inner: W,
+    progress: Progress<P>,
+    label: String,
+}
+
+impl<W: Write, P: Write> ProgressWriter<W, P> {
+    pub fn new(inner: W, progress: Progress<P>, label: &str) -> Self {
+        Self {
+            inner,
+            progress,
+            label: label.to_string(),
+        }
+    }
+}
+
+impl<W: Write, P: Write> Write for ProgressWriter<W, P> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.progress.advance_by(n, &self.label);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_progress_when_enabled() {
+        let mut progress = Progress::new(Vec::new(), true, 2);
+        progress.advance("first.png");
+        progress.advance("second.png");
+
+        let output = String::from_utf8(progress.out).unwrap();
+
+        assert_eq!(output, "[ 50%] 1/2 first.png\n[100%] 2/2 second.png\n");
+    }
+
+    #[test]
+    fn report_bytes_read_when_percentage_changes() {
+        let data = [0u8; 200];
+        let mut reader =
+            ProgressReader::new(&data[..], Progress::new(Vec::new(), true, 200), "big.png");
+        let mut buf = [0u8; 1];
+        reader.read_exact(&mut buf).unwrap();
+        let mut buf = [0u8; 99];
+        reader.read_exact(&mut buf).unwrap();
+        let mut buf = [0u8; 100];
+        reader.read_exact(&mut buf).unwrap();
+
+        let output = String::from_utf8(reader.progress.out).unwrap();
+
+        assert_eq!(output, "[ 50%] 100/200 big.png\n[100%] 200/200 big.png\n");
+    }
+
+    #[test]
+    fn report_bytes_written_when_percentage_changes() {
+        let mut writer =
+            ProgressWriter::new(Vec::new(), Progress::new(Vec::new(), true, 4), "big.png");
+        writer.write_all(&[1, 2]).unwrap();
+        writer.write_all(&[3, 4]).unwrap();
+
+        assert_eq!(writer.inner, [1, 2, 3, 4]);
+        let output = String::from_utf8(writer.progress.out).unwrap();
+        assert_eq!(output, "[ 50%] 2/4 big.png\n[100%] 4/4 big.png\n");
+    }
+
+    #[test]
+    fn no_progress_when_disabled() {
+        let mut progress = Progress::new(Vec::new(), false, 2);
+        progress.advance("first.png");
+        progress.advance("second.png");
+
+        assert!(progress.out.is_empty());
+    }
+}
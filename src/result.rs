@@ -1,2 +1,190 @@
-pub type Error = Box<dyn std::error::Error>;
+#[derive(Debug)]
+pub enum Error {
+    Io {
+        path: Option<String>,
+        source: std::io::Error,
+    },
+    Utf8(std::string::FromUtf8Error),
+    Conversion(std::num::TryFromIntError),
+    CrcMismatch {
+        chunk_type: String,
+        expected: u32,
+        actual: u32,
+    },
+    ChunkNotFound(String),
+    /// A chunk type not made of exactly four bytes.
+    ChunkTypeLength(usize),
+    /// A chunk type byte which is not an ASCII letter, at a 1-based position.
+    ChunkTypeCharacter {
+        position: usize,
+        byte: u8,
+    },
+    Message(String),
+}
+
 pub type Result<T> = core::result::Result<T, Error>;
+
+impl Error {
+    /// A stable name of the variant, for programs reading the errors.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Io { .. } => "io",
+            Self::Utf8(_) => "utf8",
+            Self::Conversion(_) => "conversion",
+            Self::CrcMismatch { .. } => "crc_mismatch",
+            Self::ChunkNotFound(_) => "chunk_not_found",
+            Self::ChunkTypeLength(_) => "chunk_type_length",
+            Self::ChunkTypeCharacter { .. } => "chunk_type_character",
+            Self::Message(_) => "message",
+        }
+    }
+
+    pub fn io(path: &str, source: std::io::Error) -> Self {
+        Self::Io {
+            path: Some(path.to_string()),
+            source,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io {
+                path: Some(path),
+                source,
+            } => write!(f, "{}: {}", path, source),
+            Self::Io { path: None, source } => write!(f, "{}", source),
+            Self::Utf8(source) => write!(f, "data is not valid UTF-8: {}", source),
+            Self::Conversion(source) => write!(f, "{}", source),
+            Self::CrcMismatch {
+                chunk_type,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "CRC mismatch in chunk '{}': expected {}, got {}",
+                chunk_type, expected, actual
+            ),
+            Self::ChunkNotFound(chunk_type) => {
+                write!(f, "no chunk with such type '{}'", chunk_type)
+            }
+            Self::ChunkTypeLength(length) => {
+                write!(f, "chunk type should be 4 bytes long, got {}", length)
+            }
+            Self::ChunkTypeCharacter { position, byte } => write!(
+                f,
+                "character {} ('{}') is not an ASCII letter",
+                position,
+                std::ascii::escape_default(*byte)
+            ),
+            Self::Message(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Utf8(source) => Some(source),
+            Self::Conversion(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(source: std::io::Error) -> Self {
+        Self::Io { path: None, source }
+    }
+}
+
+impl From<std::string::FromUtf8Error> for Error {
+    fn from(source: std::string::FromUtf8Error) -> Self {
+        Self::Utf8(source)
+    }
+}
+
+impl From<std::num::TryFromIntError> for Error {
+    fn from(source: std::num::TryFromIntError) -> Self {
+        Self::Conversion(source)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Self::Message(message.to_string())
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Self::Message(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::*;
+
+    #[test]
+    fn display_crc_mismatch() {
+        let error = Error::CrcMismatch {
+            chunk_type: "teSt".into(),
+            expected: 2799226543,
+            actual: 1,
+        };
+
+        let message = error.to_string();
+
+        assert!(message.contains("teSt"));
+        assert!(message.contains("expected 2799226543"));
+        assert!(message.contains("got 1"));
+    }
+
+    #[test]
+    fn display_io_error_with_path() {
+        let source = std::io::Error::new(std::io::ErrorKind::NotFound, "not found");
+        let error = Error::io("input.png", source);
+
+        assert_eq!(error.to_string(), "input.png: not found");
+        assert!(error.source().is_some());
+    }
+
+    #[test]
+    fn display_chunk_type_character() {
+        let error = Error::ChunkTypeCharacter {
+            position: 2,
+            byte: b'2',
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "character 2 ('2') is not an ASCII letter"
+        );
+    }
+
+    #[test]
+    fn code_of_variants() {
+        let error = Error::CrcMismatch {
+            chunk_type: "teSt".into(),
+            expected: 2799226543,
+            actual: 1,
+        };
+
+        assert_eq!(error.code(), "crc_mismatch");
+        assert_eq!(Error::from("wrong header").code(), "message");
+        assert_eq!(Error::ChunkTypeLength(3).code(), "chunk_type_length");
+    }
+
+    #[test]
+    fn message_has_no_source() {
+        let error = Error::from("wrong header");
+
+        assert_eq!(error.to_string(), "wrong header");
+        assert!(error.source().is_none());
+    }
+}
@@ -0,0 +1,206 @@
+use std::io::{Read, Write};
+
+use chacha20poly1305::aead::{Aead, Generate, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use sha2::Sha256;
+
+use crate::{chunk::Chunk, chunk_type::ChunkType, result::*};
+
+const COMPRESSED: u8 = 0b01;
+const ENCRYPTED: u8 = 0b10;
+
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+const KDF_ROUNDS: u32 = 100_000;
+
+/// A payload framed the png-secret way:
+/// `SECRET_MAGIC | version | flags | [salt | nonce] | body`,
+/// where the salt and the nonce are present only for encrypted secrets.
+pub struct Secret {
+    payload: Vec<u8>,
+    compressed: bool,
+    password: Option<String>,
+}
+
+impl Secret {
+    pub const VERSION: u8 = 1;
+
+    pub fn new(payload: Vec<u8>) -> Self {
+        Self {
+            payload,
+            compressed: false,
+            password: None,
+        }
+    }
+
+    pub fn compress(mut self, compressed: bool) -> Self {
+        self.compressed = compressed;
+        self
+    }
+
+    pub fn encrypt(mut self, password: &str) -> Self {
+        self.password = Some(password.to_string());
+        self
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    pub fn into_payload(self) -> Vec<u8> {
+        self.payload
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.compressed
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.password.is_some()
+    }
+
+    pub fn into_chunk(self, chunk_type: ChunkType) -> Result<Chunk> {
+        let mut flags = 0;
+        if self.compressed {
+            flags |= COMPRESSED;
+        }
+        if self.password.is_some() {
+            flags |= ENCRYPTED;
+        }
+
+        let mut data = Chunk::SECRET_MAGIC.to_vec();
+        data.extend([Self::VERSION, flags]);
+
+        let mut body = self.payload;
+        if self.compressed {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(&body)?;
+            body = encoder.finish()?;
+        }
+        if let Some(password) = &self.password {
+            let salt = <[u8; SALT_LENGTH]>::generate();
+            let nonce = Nonce::generate();
+            body = cipher(password, &salt)
+                .encrypt(&nonce, &body[..])
+                .map_err(|_| "cannot encrypt secret")?;
+            data.extend(salt);
+            data.extend(nonce);
+        }
+
+        data.append(&mut body);
+        Ok(Chunk::new(chunk_type, data))
+    }
+
+    pub fn from_chunk(chunk: &Chunk, password: Option<&str>) -> Result<Secret> {
+        if !chunk.is_secret_frame() {
+            Err("not a png-secret frame")?
+        }
+
+        let bytes = &chunk.data()[Chunk::SECRET_MAGIC.len()..];
+        let (version, flags, mut body) = match bytes {
+            [version, flags, body @ ..] => (*version, *flags, body.to_vec()),
+            _ => Err("truncated secret frame")?,
+        };
+
+        if version != Self::VERSION {
+            Err(format!("unsupported secret version {}", version))?
+        }
+
+        let encrypted = flags & ENCRYPTED != 0;
+        if encrypted {
+            let password = password.ok_or("secret is encrypted, a password is required")?;
+            if body.len() < SALT_LENGTH + NONCE_LENGTH {
+                Err("truncated secret frame")?
+            }
+            let (salt, rest) = body.split_at(SALT_LENGTH);
+            let (nonce, ciphertext) = rest.split_at(NONCE_LENGTH);
+            let nonce = Nonce::try_from(nonce).map_err(|_| "truncated secret frame")?;
+            body = cipher(password, salt)
+                .decrypt(&nonce, ciphertext)
+                .map_err(|_| "wrong password or corrupted secret")?;
+        }
+
+        let compressed = flags & COMPRESSED != 0;
+        if compressed {
+            let mut payload = Vec::new();
+            ZlibDecoder::new(&body[..]).read_to_end(&mut payload)?;
+            body = payload;
+        }
+
+        Ok(Self {
+            payload: body,
+            compressed,
+            password: password.filter(|_| encrypted).map(str::to_string),
+        })
+    }
+}
+
+fn cipher(password: &str, salt: &[u8]) -> ChaCha20Poly1305 {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, KDF_ROUNDS, &mut key);
+    ChaCha20Poly1305::new(&Key::from(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn round_trip(compressed: bool, password: Option<&str>) {
+        let payload = "test data ".repeat(10).into_bytes();
+        let mut secret = Secret::new(payload.clone()).compress(compressed);
+        if let Some(password) = password {
+            secret = secret.encrypt(password);
+        }
+
+        let chunk = secret
+            .into_chunk(ChunkType::from_str("ruSt").unwrap())
+            .unwrap();
+        let secret = Secret::from_chunk(&chunk, password).unwrap();
+
+        assert!(chunk.is_secret_frame());
+        assert_eq!(secret.payload(), payload);
+        assert_eq!(secret.is_compressed(), compressed);
+        assert_eq!(secret.is_encrypted(), password.is_some());
+    }
+
+    #[test]
+    fn round_trip_plain() {
+        round_trip(false, None);
+    }
+
+    #[test]
+    fn round_trip_compressed() {
+        round_trip(true, None);
+    }
+
+    #[test]
+    fn round_trip_encrypted() {
+        round_trip(false, Some("password"));
+    }
+
+    #[test]
+    fn round_trip_compressed_and_encrypted() {
+        round_trip(true, Some("password"));
+    }
+
+    #[test]
+    fn err_from_chunk_with_wrong_password() {
+        let chunk = Secret::new("test data".into())
+            .encrypt("password")
+            .into_chunk(ChunkType::from_str("ruSt").unwrap())
+            .unwrap();
+
+        assert!(Secret::from_chunk(&chunk, Some("wrong")).is_err());
+        assert!(Secret::from_chunk(&chunk, None).is_err());
+    }
+
+    #[test]
+    fn err_from_plain_chunk() {
+        let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), "test data".into());
+
+        assert!(Secret::from_chunk(&chunk, None).is_err());
+    }
+}
@@ -0,0 +1,124 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+use pngsecret::{Chunk, ChunkType, Png};
+
+#[test]
+fn err_print_non_png_file_shows_clean_message() {
+    let path = std::env::temp_dir().join(format!("pngsecret-cli-{}.txt", std::process::id()));
+    std::fs::write(&path, "not a png file").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_pngsecret"))
+        .arg("print")
+        .arg(&path)
+        .output()
+        .unwrap();
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(stderr, "pngsecret: wrong header\n");
+}
+
+#[test]
+fn err_crc_mismatch_with_json_errors() {
+    let chunk = Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"secret".to_vec());
+    let mut bytes = Png::STANDARD_HEADER.to_vec();
+    bytes.extend(chunk.as_bytes());
+    let last = bytes.len() - 1;
+    bytes[last] ^= 1;
+    let path = std::env::temp_dir().join(format!("pngsecret-cli-{}-crc.png", std::process::id()));
+    std::fs::write(&path, &bytes).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_pngsecret"))
+        .arg("print")
+        .arg(&path)
+        .arg("--json-errors")
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["code"], "crc_mismatch");
+    assert_eq!(error["chunk_type"], "ruSt");
+    assert_eq!(error["expected_crc"], chunk.crc());
+    assert_eq!(error["actual_crc"], chunk.crc() ^ 1);
+}
+
+#[test]
+fn err_unknown_argument_with_json_errors() {
+    let output = Command::new(env!("CARGO_BIN_EXE_pngsecret"))
+        .arg("print")
+        .arg("--json-errors")
+        .arg("--no-such-flag")
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["code"], "usage");
+    assert!(error["message"]
+        .as_str()
+        .unwrap()
+        .contains("unexpected argument '--no-such-flag'"));
+}
+
+#[test]
+fn pipe_from_stdin_to_stdout() {
+    let png = Png::from_chunks(vec![
+        Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"secret".to_vec()),
+        Chunk::new(ChunkType::from_str("IEND").unwrap(), vec![]),
+    ]);
+    let mut bytes = png.as_bytes();
+    bytes.extend(b"trailing data");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_pngsecret"))
+        .arg("pipe")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(&bytes).unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, bytes);
+}
+
+#[test]
+fn batch_progress_is_gated_by_quiet() {
+    let png = Png::from_chunks(vec![Chunk::new(
+        ChunkType::from_str("IEND").unwrap(),
+        vec![],
+    )]);
+    let paths: Vec<_> = ["first", "second"]
+        .iter()
+        .map(|name| {
+            let path = std::env::temp_dir().join(format!(
+                "pngsecret-cli-{}-progress-{}.png",
+                std::process::id(),
+                name
+            ));
+            std::fs::write(&path, png.as_bytes()).unwrap();
+            path
+        })
+        .collect();
+    let run = |quiet: bool| {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_pngsecret"));
+        command.arg("entropy").args(&paths);
+        if quiet {
+            command.arg("--quiet");
+        }
+        command.output().unwrap()
+    };
+
+    let output = run(false);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("[100%] 2/2 "));
+
+    let output = run(true);
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+}